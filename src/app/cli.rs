@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use super::context::{analysis, diff, lint, parity, LevelData};
+
+/// Run a command from the terminal without opening the editor. Commands start with `--`, so any
+/// level file can still be opened by name. Returns `None` if the arguments aren't a command (e.g.
+/// a level to open), otherwise the exit code of the command
+pub fn run(args: &[String]) -> Option<Result<i32, String>> {
+    let (command, args) = args.split_first()?;
+
+    match command.as_str() {
+        "--parity" => Some(check_parity(args)),
+        "--stars" => Some(check_stars(args)),
+        "--sensitivity" => Some(check_sensitivity(args)),
+        "--lint" => Some(check_lint(args)),
+        "--diff" => Some(diff_levels(args)),
+        "--merge" => Some(merge_levels(args)),
+        _ => None,
+    }
+}
+
+/// Check the parity simulation against its regression snapshots
+fn check_parity(args: &[String]) -> Result<i32, String> {
+    let dir = args.first().map_or("parity", String::as_str);
+
+    let mut failures = 0;
+    for (path, result) in parity::check_corpus(Path::new(dir))? {
+        match result {
+            Ok(()) => println!("ok      {path}"),
+            Err(e) => {
+                println!("FAILED  {path}: {e}");
+                failures += 1;
+            }
+        }
+    }
+
+    Ok(i32::from(failures > 0))
+}
+
+/// Check whether every star in each level can be collected in one launch
+fn check_stars(args: &[String]) -> Result<i32, String> {
+    if args.is_empty() {
+        return Err(String::from("Usage: --stars <level>..."));
+    }
+
+    let mut failures = 0;
+    for path in args {
+        let level_data = LevelData::load(path)?;
+        let search = analysis::collect_every_star(&level_data);
+
+        if search.collects_every_star() {
+            let launch = search.launch;
+            println!(
+                "ok      {path}: all {} stars with a launch of ({:.2}, {:.2})",
+                search.total, launch.x, launch.y
+            );
+        } else {
+            println!(
+                "FAILED  {path}: at most {} of {} stars in one launch",
+                search.collected, search.total
+            );
+            failures += 1;
+        }
+    }
+
+    Ok(i32::from(failures > 0))
+}
+
+/// Report how far each level's intended launch, planets and walls can be changed before the
+/// launch ends differently
+fn check_sensitivity(args: &[String]) -> Result<i32, String> {
+    if args.is_empty() {
+        return Err(String::from("Usage: --sensitivity <level>..."));
+    }
+
+    let mut failures = 0;
+    for path in args {
+        let level_data = LevelData::load(path)?;
+
+        if let Some(solution) = level_data.solution {
+            println!("{path}");
+            print!("{}", analysis::sensitivity(&level_data, solution));
+        } else {
+            println!("FAILED  {path}: no intended launch marked");
+            failures += 1;
+        }
+    }
+
+    Ok(i32::from(failures > 0))
+}
+
+/// Check each level for mistakes, failing if any has an error
+fn check_lint(args: &[String]) -> Result<i32, String> {
+    if args.is_empty() {
+        return Err(String::from("Usage: --lint <level>..."));
+    }
+
+    let mut failures = 0;
+    for path in args {
+        let level_data = LevelData::load(path)?;
+        let diagnostics = lint::check(&level_data, true);
+
+        if diagnostics.is_empty() {
+            println!("ok      {path}");
+        }
+        for diagnostic in &diagnostics {
+            println!("{path}: {diagnostic}");
+        }
+
+        if diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == lint::Severity::Error)
+        {
+            failures += 1;
+        }
+    }
+
+    Ok(i32::from(failures > 0))
+}
+
+/// Report the planets and walls added, removed and moved between two versions of a level. Like
+/// `diff`, this fails if there are any differences
+fn diff_levels(args: &[String]) -> Result<i32, String> {
+    let [old_path, new_path] = args else {
+        return Err(String::from("Usage: --diff <old level> <new level>"));
+    };
+
+    let old = LevelData::load(old_path)?;
+    let new = LevelData::load(new_path)?;
+    let diff = diff::diff(&old, &new);
+    print!("{diff}");
+
+    Ok(i32::from(!diff.is_empty()))
+}
+
+/// Merge the changes made to a level on two sides, saving the result over our side. This works as
+/// a git merge driver, and fails if anything was changed differently on each side
+fn merge_levels(args: &[String]) -> Result<i32, String> {
+    let [base_path, ours_path, theirs_path] = args else {
+        return Err(String::from(
+            "Usage: --merge <base level> <our level> <their level>",
+        ));
+    };
+
+    let base = LevelData::load(base_path)?;
+    let ours = LevelData::load(ours_path)?;
+    let theirs = LevelData::load(theirs_path)?;
+
+    let merge = diff::merge(&base, &ours, &theirs);
+    merge.level_data.save(ours_path)?;
+
+    for conflict in &merge.conflicts {
+        eprintln!("CONFLICT {ours_path}: {conflict}");
+    }
+
+    Ok(i32::from(!merge.conflicts.is_empty()))
+}
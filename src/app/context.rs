@@ -1,18 +1,50 @@
+pub mod analysis;
+pub mod diff;
+pub mod ghost;
+
 mod app_state;
 pub use app_state::AppState;
 
 mod event;
+
+mod field_overlay;
+pub use field_overlay::FieldOverlay;
+
+pub use ghost::Ghost;
 mod global_keybinds;
 
+pub mod history;
+pub use history::History;
+
+pub mod lint;
+
 mod level_data;
-pub use level_data::LevelData;
+pub use level_data::{LevelData, Symmetry, SymmetryMode};
+
+mod replay;
+pub use replay::{Input as ReplayInput, Playback, Replay};
 
 mod selection;
 pub use selection::{SelectedBody, Selection, WallEnd};
 
 mod simulation;
-pub use simulation::{Event as SimulationEvent, Planet, Player, Simulation, Target, Vec2F, Wall};
+pub use simulation::{
+    parity, position_at, Anchor, Burn, Cause as SimulationCause, Checkpoint,
+    Event as SimulationEvent, Integrator, Limits, Motion, PhysicsProfile, Planet, PlanetKind,
+    Player, Simulation, Star, Target, Vec2F, Wall, WallKind, WallMotion, Wormhole, Zone,
+    ZoneEffect, MAX_PLANET_MASS, THRUST,
+};
+
+mod task;
+use task::{Finding, Task};
+
+pub mod timeline;
+pub use timeline::Timeline;
+
+use global_keybinds::incremental_path;
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct Context {
     pub state: AppState,
     pub level_path: String,
@@ -21,6 +53,33 @@ pub struct Context {
     pub edit_selection: Selection,
     pub show_hints: bool,
     pub show_background_image: bool,
+    /// Plot the energy of the predicted flight under each integrator
+    pub show_energy_plot: bool,
+    /// Which view of the planets' gravity is drawn over the level
+    pub field_overlay: FieldOverlay,
+    /// How many ticks into the flight the Aim Mode preview shows moving planets at
+    pub aim_time: u32,
+    /// The launch being flown, until it's saved as a replay
+    pub recording: Option<Replay>,
+    /// The replay being watched, if any
+    pub playback: Option<Playback>,
+    /// Launches pinned to compare against the one being aimed
+    pub ghosts: Vec<Ghost>,
+    /// Where the ghosts fly through the level as it is now
+    ghost_flights: Option<ghost::Flights>,
+    /// Snapshots of the flight, so it can be stepped back through
+    pub timeline: Timeline,
+    /// The last sensitivity analysis of the intended launch, with the [`replay::level_hash`] of
+    /// the level it was run on
+    sensitivity: Option<(u64, analysis::Sensitivity)>,
+    /// What's wrong with the level, with the [`replay::level_hash`] of the level that was checked
+    lint: (u64, Vec<lint::Diagnostic>),
+    /// Another version of the level, drawn over it to show what's changed
+    pub comparison: Option<diff::Comparison>,
+    /// The saved versions of the level, while the history panel is open
+    pub history: Option<History>,
+    /// The slow check running in the background, if any
+    task: Option<Task>,
 }
 
 impl Context {
@@ -35,6 +94,19 @@ impl Context {
             edit_selection: Selection::new(),
             show_hints: false,
             show_background_image: true,
+            show_energy_plot: false,
+            field_overlay: FieldOverlay::Off,
+            aim_time: 0,
+            recording: None,
+            playback: None,
+            ghosts: Vec::new(),
+            ghost_flights: None,
+            timeline: Timeline::new(),
+            sensitivity: None,
+            lint: (0, Vec::new()),
+            comparison: None,
+            history: None,
+            task: None,
         }
     }
 
@@ -42,26 +114,476 @@ impl Context {
         self.level_path = String::from(filepath);
         self.level_data = LevelData::load(filepath)?;
         self.state = AppState::Editing;
+        self.forget_level();
 
         println!("Loaded level {filepath}");
 
         Ok(())
     }
 
+    /// Drop everything worked out about the level that was open, once another replaces it
+    pub fn forget_level(&mut self) {
+        self.history = None;
+        self.ghosts.clear();
+        self.ghost_flights = None;
+        self.comparison = None;
+        self.sensitivity = None;
+        self.task = None;
+    }
+
     /// Saves the level to the new filepath, or the old one if `new_path` is None
     pub fn save(&mut self, new_path: Option<String>) -> Result<(), String> {
         if let Some(path) = new_path {
             self.level_path = path;
         }
 
-        self.level_data.save(&self.level_path)
+        self.level_data.save(&self.level_path)?;
+
+        // Only the quick rules, so saving stays instant. The findings of a thorough check are
+        // kept until the level changes
+        if self.lint.0 != replay::level_hash(&self.level_data) {
+            self.check_level();
+        }
+
+        for diagnostic in self.diagnostics() {
+            println!("{diagnostic}");
+        }
+
+        Ok(())
+    }
+
+    /// Save the level as the next incremental save of the file being edited, remembering the file
+    /// the saves started from so only its own versions are listed in the history
+    pub fn save_incrementally(&mut self) -> Result<(), String> {
+        let lineage = self.lineage();
+        self.level_data.lineage = Some(lineage);
+
+        self.save(Some(incremental_path::generate(&self.level_path)?))
+    }
+
+    /// The file the level being edited was first saved as
+    fn lineage(&self) -> String {
+        self.level_data
+            .lineage
+            .clone()
+            .unwrap_or_else(|| incremental_path::file_name(&self.level_path))
+    }
+
+    /// Check the level for the quick mistakes. The thorough check, which flies every launch, is
+    /// only run in the background by `lint_level`
+    pub fn check_level(&mut self) {
+        let diagnostics = lint::check(&self.level_data, false);
+
+        self.lint = (replay::level_hash(&self.level_data), diagnostics);
+    }
+
+    /// Run every lint rule, including the slow ones, in the background
+    pub fn lint_level(&mut self) {
+        self.start_task("Checking every launch", |level_data| {
+            Finding::Lint(lint::check(level_data, true))
+        });
+    }
+
+    /// Keep and print what the thorough check found, as long as the level hasn't changed since
+    fn linted(&mut self, diagnostics: Vec<lint::Diagnostic>, level_hash: u64) {
+        if level_hash != replay::level_hash(&self.level_data) {
+            eprintln!("The level changed while checking it, check again with Ctrl+L");
+            return;
+        }
+
+        self.lint = (level_hash, diagnostics);
+
+        if self.diagnostics().is_empty() {
+            println!("No problems found");
+        }
+
+        for diagnostic in self.diagnostics() {
+            println!("{diagnostic}");
+        }
+    }
+
+    /// What's wrong with the level, as of the last check
+    pub fn diagnostics(&self) -> &[lint::Diagnostic] {
+        &self.lint.1
     }
 
     pub fn tick(&mut self) {
+        self.poll_task();
+
+        if matches!(self.state, AppState::Editing | AppState::Aiming) {
+            self.fly_ghosts();
+        }
+
+        if let Some(playback) = &mut self.playback {
+            if !matches!(self.state, AppState::Flying | AppState::GameOver(_)) {
+                self.playback = None;
+                return;
+            }
+
+            playback.advance();
+            self.simulation = playback.simulation();
+            self.state = playback
+                .event()
+                .map_or(AppState::Flying, AppState::GameOver);
+
+            return;
+        }
+
         if matches!(self.state, AppState::Flying) {
-            if let Some(simulation_event) = self.simulation.tick() {
-                self.state = AppState::GameOver(simulation_event);
+            self.fly();
+        }
+
+        if matches!(self.state, AppState::Editing)
+            && self.lint.0 != replay::level_hash(&self.level_data)
+        {
+            self.check_level();
+        }
+    }
+
+    /// Run `work` on a worker thread, unless one is already busy
+    fn start_task(
+        &mut self,
+        name: &'static str,
+        work: impl FnOnce(&LevelData) -> Finding + Send + 'static,
+    ) {
+        if let Some(task) = &self.task {
+            eprintln!("Still {}...", task.name.to_lowercase());
+            return;
+        }
+
+        println!("{name}...");
+        self.task = Some(Task::spawn(name, &self.level_data, work));
+    }
+
+    /// What the worker thread is doing, if anything
+    pub fn task_status(&self) -> Option<&str> {
+        self.task.as_ref().map(|task| task.name)
+    }
+
+    /// Use what the worker found, once it's done
+    fn poll_task(&mut self) {
+        let Some(result) = self.task.as_ref().and_then(Task::poll) else {
+            return;
+        };
+        let Some(task) = self.task.take() else {
+            return;
+        };
+
+        match result {
+            Ok(Finding::Stars(search)) => self.found_stars(search, task.level_hash),
+            Ok(Finding::Sensitivity(sensitivity)) => {
+                if task.level_hash != replay::level_hash(&self.level_data) {
+                    eprintln!("The level changed while checking it, check again with Shift+M");
+                    return;
+                }
+
+                print!("{sensitivity}");
+                self.sensitivity = Some((task.level_hash, sensitivity));
+            }
+            Ok(Finding::Lint(diagnostics)) => self.linted(diagnostics, task.level_hash),
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+
+    /// Fly the simulation on by a frame, keeping a snapshot for the timeline
+    fn fly(&mut self) {
+        if let Some(simulation_event) = self.simulation.tick() {
+            self.state = AppState::GameOver(simulation_event);
+            self.timeline.end = Some(simulation_event);
+            self.save_replay();
+        };
+
+        self.timeline.record(&self.simulation);
+    }
+
+    /// Move the flight `change` ticks forwards or backwards and pause it there. Stepping past the
+    /// furthest the flight has got flies it on a tick at a time
+    pub fn step_flight(&mut self, change: i32) {
+        let furthest = self
+            .timeline
+            .furthest
+            .take()
+            .unwrap_or_else(|| self.simulation.clone());
+        let tick = self.simulation.ticks.saturating_add_signed(change);
+
+        if tick <= furthest.ticks {
+            self.simulation = self.timeline.at(tick, &furthest);
+            self.state = match self.timeline.end {
+                Some(event) if tick >= event.tick => AppState::GameOver(event),
+                _ => AppState::Flying,
             };
+
+            if tick < furthest.ticks {
+                self.timeline.furthest = Some(furthest);
+            }
+        } else {
+            self.simulation = furthest;
+            let speed = self.simulation.speed;
+            self.simulation.speed = 1;
+            self.simulation.playing = true;
+
+            while self.simulation.ticks < tick && matches!(self.state, AppState::Flying) {
+                self.fly();
+            }
+
+            self.simulation.speed = speed;
+        }
+
+        self.simulation.playing = false;
+    }
+
+    /// Carry on flying from the tick being looked at, forgetting what happened after it
+    pub fn resume_flight(&mut self) {
+        let tick = self.simulation.ticks;
+        self.timeline.truncate(tick);
+
+        if let Some(recording) = &mut self.recording {
+            recording.inputs.retain(|(at, _)| *at <= tick);
+        }
+
+        self.simulation.playing = true;
+    }
+
+    /// Save the flight that's just ended as a replay next to the level. Levels that haven't been
+    /// saved yet have nowhere to keep replays
+    pub fn save_replay(&mut self) {
+        let Some(mut replay) = self.recording.take() else {
+            return;
+        };
+
+        replay.inputs.extend(
+            self.simulation
+                .inputs
+                .iter()
+                .map(|input| (input.tick, ReplayInput::Thrust(input.direction))),
+        );
+        replay.inputs.sort_by_key(|(tick, _)| *tick);
+
+        if !std::path::Path::new(&self.level_path).exists() {
+            return;
+        }
+
+        if let Err(e) = replay.save(&self.level_path) {
+            eprintln!("Failed to save replay: {e}");
+        }
+    }
+
+    /// Pin the launch being aimed as a ghost, or with `from_replay`, the last replay recorded
+    pub fn pin_ghost(&mut self, from_replay: bool) {
+        let replay = if from_replay {
+            match Replay::load_all(&self.level_path).map(|mut replays| replays.pop()) {
+                Ok(Some(replay)) => replay,
+                Ok(None) => return,
+                Err(e) => {
+                    eprintln!("{e}");
+                    return;
+                }
+            }
+        } else {
+            Replay::new(&self.level_data)
+        };
+
+        let ghost = Ghost::new(replay, &self.level_data, &self.ghosts);
+        println!("Pinned ghost {} ({})", ghost.name, ghost.pinned);
+        self.ghosts.push(ghost);
+        self.ghost_flights = None;
+    }
+
+    /// Unpin every ghost
+    pub fn clear_ghosts(&mut self) {
+        self.ghosts.clear();
+        self.ghost_flights = None;
+    }
+
+    /// Fly the ghosts again if the level has changed since they were last flown
+    fn fly_ghosts(&mut self) {
+        if self.ghosts.is_empty() {
+            self.ghost_flights = None;
+            return;
+        }
+
+        match &mut self.ghost_flights {
+            Some(flights) => flights.update(&self.ghosts, &self.level_data),
+            None => self.ghost_flights = Some(ghost::Flights::new(&self.ghosts, &self.level_data)),
+        }
+    }
+
+    /// Where the ghosts fly through the level, as of the last tick
+    pub const fn ghost_flights(&self) -> Option<&ghost::Flights> {
+        self.ghost_flights.as_ref()
+    }
+
+    /// Mark the launch being aimed as the one the level is designed to be solved with
+    pub fn mark_solution(&mut self) {
+        let launch = self.level_data.player.velocity;
+        self.level_data.solution = Some(launch);
+
+        let cause = analysis::fly(&self.level_data, launch, |_| false)
+            .1
+            .map_or(SimulationCause::Timeout, |event| event.cause);
+        println!("Marked the intended launch, which ends at {cause}");
+    }
+
+    /// Find how far the intended launch and every body can be changed before the launch ends
+    /// differently
+    pub fn analyse_sensitivity(&mut self) {
+        let Some(solution) = self.level_data.solution else {
+            eprintln!("Mark the intended launch with M first");
+            return;
+        };
+
+        self.start_task("Checking sensitivity", move |level_data| {
+            Finding::Sensitivity(analysis::sensitivity(level_data, solution))
+        });
+    }
+
+    /// The last sensitivity analysis, as long as the level hasn't changed since
+    pub fn sensitivity(&self) -> Option<&analysis::Sensitivity> {
+        self.sensitivity
+            .as_ref()
+            .filter(|(hash, _)| *hash == replay::level_hash(&self.level_data))
+            .map(|(_, sensitivity)| sensitivity)
+    }
+
+    /// Draw `comparison` over the level, or stop comparing if there was an error getting it
+    pub fn compare_with(&mut self, comparison: Result<diff::Comparison, String>) {
+        match comparison {
+            Ok(comparison) => {
+                print!(
+                    "Comparing with {}\n{}",
+                    comparison.name,
+                    diff::diff(&comparison.level_data, &self.level_data)
+                );
+                self.comparison = Some(comparison);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                self.comparison = None;
+            }
+        }
+    }
+
+    /// Open the panel listing the saved versions of the level, or close it
+    pub fn toggle_history(&mut self) {
+        if self.history.take().is_some() {
+            return;
+        }
+
+        let history = History::new(&self.level_path);
+        if history.versions.is_empty() {
+            eprintln!("This level hasn't been saved yet");
+            return;
         }
+
+        self.state = AppState::Editing;
+        self.history = Some(history);
+    }
+
+    /// List the saved versions again, keeping the same one highlighted if it's still there
+    fn refresh_history(&mut self) {
+        let selected = self
+            .history
+            .as_ref()
+            .and_then(History::selected)
+            .map(|version| version.path.clone());
+        let old_index = self.history.as_ref().map_or(0, |history| history.selected);
+
+        let mut history = History::new(&self.level_path);
+        history.selected = history
+            .versions
+            .iter()
+            .position(|version| Some(&version.path) == selected.as_ref())
+            .unwrap_or_else(|| old_index.min(history.versions.len().saturating_sub(1)));
+        self.history = Some(history);
+    }
+
+    /// Edit the highlighted version again, saving it as a new incremental save so that no other
+    /// version is lost
+    pub fn restore_version(&mut self) -> Result<(), String> {
+        let Some(version) = self.history.as_ref().and_then(History::selected) else {
+            return Ok(());
+        };
+        let label = version.label();
+        let level_data = version.level_data.clone()?;
+        let lineage = self.lineage();
+
+        self.level_data = LevelData {
+            lineage: Some(lineage),
+            ..level_data
+        };
+        self.save_incrementally()?;
+        println!("Restored version {label} as {}", self.level_path);
+        self.refresh_history();
+
+        Ok(())
+    }
+
+    /// Delete the file of the highlighted version, or with `older`, of every version older than
+    /// it. The level being edited is never deleted
+    pub fn prune_versions(&mut self, older: bool) {
+        let Some(history) = &self.history else {
+            return;
+        };
+
+        let paths = if older {
+            history.older(&self.level_path)
+        } else {
+            history
+                .selected()
+                .filter(|version| version.path != self.level_path)
+                .map(|version| vec![version.path.clone()])
+                .unwrap_or_default()
+        };
+        if paths.is_empty() {
+            eprintln!("Nothing to prune");
+            return;
+        }
+
+        let answer = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("Delete Versions")
+            .set_description(format!(
+                "Delete these files? This can't be undone.\n\n{}",
+                paths.join("\n")
+            ))
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        if answer != MessageDialogResult::Yes {
+            println!("Kept every version");
+            return;
+        }
+
+        for path in &paths {
+            match std::fs::remove_file(path) {
+                Ok(()) => println!("Deleted {path}"),
+                Err(e) => eprintln!("Failed to delete {path}: {e}"),
+            }
+        }
+
+        self.refresh_history();
+    }
+
+    /// Watch the last replay recorded for the level
+    pub fn watch_replay(&mut self) {
+        let replay = match Replay::load_all(&self.level_path) {
+            Ok(mut replays) => replays.pop(),
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        };
+        let Some(replay) = replay else {
+            eprintln!("No replays recorded for this level");
+            return;
+        };
+
+        if replay.level_hash != replay::level_hash(&self.level_data) {
+            println!("This replay was recorded on a different version of the level");
+        }
+
+        let playback = Playback::new(replay, &self.level_data);
+        self.simulation = playback.simulation();
+        self.playback = Some(playback);
+        self.state = AppState::Flying;
     }
 }
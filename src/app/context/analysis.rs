@@ -0,0 +1,285 @@
+//! Searching every launch a level allows for ones that do something in particular, and how much
+//! a level can change before a launch stops working
+
+use std::{f64::consts::FRAC_PI_4, fmt::Display};
+
+use super::{
+    LevelData, PhysicsProfile, SelectedBody, Simulation, SimulationCause, SimulationEvent, Vec2F,
+    WallEnd,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The difference in launch power between searched launches
+const POWER_STEP: f64 = 0.1;
+
+/// How far, in degrees, the intended launch is turned each way looking for where it flips, and
+/// in what steps
+const ANGLE_RANGE: f64 = 10.0;
+const ANGLE_NUDGE: f64 = 0.1;
+/// How far the intended launch's power is changed each way, and in what steps
+const POWER_RANGE: f64 = 1.0;
+const POWER_NUDGE: f64 = 0.01;
+/// How far each planet and wall is moved looking for where the intended launch flips, in eight
+/// directions, and in what steps
+const MOVE_RANGE: f64 = 10.0;
+const MOVE_NUDGE: f64 = 0.5;
+
+/// Every launch the search tries: each whole degree, at every power from the weakest to the
+/// strongest in steps of [`POWER_STEP`]
+pub fn launches(physics: &PhysicsProfile) -> impl Iterator<Item = Vec2F> {
+    let powers = ((physics.max_power - physics.min_power) / POWER_STEP).round() as u32 + 1;
+    let min_power = physics.min_power;
+
+    (0..360).flat_map(move |degree| {
+        let angle = f64::from(degree).to_radians();
+
+        (0..powers).map(move |step| {
+            Vec2F::new(angle.cos(), angle.sin()) * f64::from(step).mul_add(POWER_STEP, min_power)
+        })
+    })
+}
+
+/// Fly the level with the given launch until the flight ends or `stop` returns true
+pub fn fly(
+    level_data: &LevelData,
+    launch: Vec2F,
+    stop: impl Fn(&Simulation) -> bool,
+) -> (Simulation, Option<SimulationEvent>) {
+    let mut simulation = Simulation::empty();
+    simulation.push(level_data);
+    simulation.player.velocity = launch;
+
+    while !stop(&simulation) {
+        if let Some(event) = simulation.tick() {
+            return (simulation, Some(event));
+        }
+    }
+
+    (simulation, None)
+}
+
+/// The launch that collects the most stars
+#[derive(Debug, Clone, Copy)]
+pub struct StarSearch {
+    pub launch: Vec2F,
+    pub collected: usize,
+    pub total: usize,
+}
+
+impl StarSearch {
+    pub const fn collects_every_star(&self) -> bool {
+        self.collected == self.total
+    }
+}
+
+/// Find whether one launch can collect every star in the level, or how close any comes
+pub fn collect_every_star(level_data: &LevelData) -> StarSearch {
+    let total = level_data.stars.len();
+    let mut best = StarSearch {
+        launch: level_data.player.velocity,
+        collected: 0,
+        total,
+    };
+
+    for launch in launches(&level_data.physics) {
+        let (simulation, _) = fly(level_data, launch, |simulation| {
+            simulation.stars_collected() == total
+        });
+
+        let collected = simulation.stars_collected();
+        if collected > best.collected {
+            best = StarSearch {
+                launch,
+                collected,
+                total,
+            };
+        }
+
+        if best.collects_every_star() {
+            break;
+        }
+    }
+
+    best
+}
+
+/// Find a launch that reaches the target, trying the intended launch first
+pub fn reach_target(level_data: &LevelData) -> Option<Vec2F> {
+    level_data
+        .solution
+        .into_iter()
+        .chain(launches(&level_data.physics))
+        .find(|launch| ending(level_data, *launch) == SimulationCause::Target)
+}
+
+/// How far something can be changed down or up before the intended launch ends differently.
+/// `None` means it never did within the range searched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Margin {
+    pub below: Option<f64>,
+    pub above: Option<f64>,
+}
+
+impl Margin {
+    /// The smaller of the two changes that flip the result
+    pub fn tightest(self) -> Option<f64> {
+        match (self.below, self.above) {
+            (Some(below), Some(above)) => Some(below.min(above)),
+            (below, above) => below.or(above),
+        }
+    }
+}
+
+/// How much a level can be changed before its intended launch ends differently
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensitivity {
+    pub launch: Vec2F,
+    /// How the intended launch ends
+    pub cause: SimulationCause,
+    /// How many degrees the launch can be turned anticlockwise (below) or clockwise (above)
+    pub angle: Margin,
+    pub power: Margin,
+    /// How far each planet can be moved in any direction, in the same order as the planets
+    pub planets: Vec<Option<f64>>,
+    /// How far each wall can be moved in any direction, in the same order as the walls
+    pub walls: Vec<Option<f64>>,
+}
+
+impl Sensitivity {
+    /// The smallest change to anything that flips the result, for the summary
+    pub fn tightest_move(&self) -> Option<(String, f64)> {
+        let planets = self
+            .planets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, margin)| margin.map(|margin| (format!("planet {i}"), margin)));
+        let walls = self
+            .walls
+            .iter()
+            .enumerate()
+            .filter_map(|(i, margin)| margin.map(|margin| (format!("wall {i}"), margin)));
+
+        planets.chain(walls).min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+/// The report of how far each thing can change, written `>range` where it never flipped
+impl Display for Sensitivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let amount = |margin: Option<f64>, range: f64| {
+            margin.map_or_else(|| format!(">{range}"), |margin| format!("{margin:.2}"))
+        };
+
+        writeln!(f, "The intended launch ends at {}", self.cause)?;
+        writeln!(
+            f,
+            "angle     -{} / +{} degrees",
+            amount(self.angle.below, ANGLE_RANGE),
+            amount(self.angle.above, ANGLE_RANGE)
+        )?;
+        writeln!(
+            f,
+            "power     -{} / +{}",
+            amount(self.power.below, POWER_RANGE),
+            amount(self.power.above, POWER_RANGE)
+        )?;
+
+        for (i, margin) in self.planets.iter().enumerate() {
+            writeln!(f, "planet {i:<2} {} px", amount(*margin, MOVE_RANGE))?;
+        }
+        for (i, margin) in self.walls.iter().enumerate() {
+            writeln!(f, "wall {i:<4} {} px", amount(*margin, MOVE_RANGE))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fly the launch into the level to the end, and say how it ended. Every flight times out at the
+/// latest
+fn ending(level_data: &LevelData, launch: Vec2F) -> SimulationCause {
+    fly(level_data, launch, |_| false)
+        .1
+        .map_or(SimulationCause::Timeout, |event| event.cause)
+}
+
+/// Whether two flights ended the same way. Orbits count as the same whatever their period
+fn ends_alike(a: SimulationCause, b: SimulationCause) -> bool {
+    match (a, b) {
+        (SimulationCause::Orbit(_), SimulationCause::Orbit(_)) => true,
+        _ => a == b,
+    }
+}
+
+/// The first multiple of `nudge`, up to `range`, at which `flips` is true
+fn first_flip(range: f64, nudge: f64, mut flips: impl FnMut(f64) -> bool) -> Option<f64> {
+    let steps = (range / nudge).round() as u32;
+
+    (1..=steps)
+        .map(|step| f64::from(step) * nudge)
+        .find(|amount| flips(*amount))
+}
+
+/// Find how far the launch's angle and power, and each planet and wall, can be changed before
+/// the launch ends differently, by flying it again with each of them nudged further and further
+pub fn sensitivity(level_data: &LevelData, launch: Vec2F) -> Sensitivity {
+    let cause = ending(level_data, launch);
+    let flips =
+        |level_data: &LevelData, launch: Vec2F| !ends_alike(ending(level_data, launch), cause);
+
+    let turned = |degrees: f64| flips(level_data, launch.rotated(degrees.to_radians()));
+    // The player can't launch outside the profile's power range, so neither can the scan
+    let physics = &level_data.physics;
+    let powered = |change: f64| {
+        let power = launch.magnitude() + change;
+        (physics.min_power..=physics.max_power).contains(&power)
+            && flips(level_data, launch.normalised() * power)
+    };
+
+    let angle = Margin {
+        below: first_flip(ANGLE_RANGE, ANGLE_NUDGE, |degrees| turned(-degrees)),
+        above: first_flip(ANGLE_RANGE, ANGLE_NUDGE, turned),
+    };
+    let power = Margin {
+        below: first_flip(POWER_RANGE, POWER_NUDGE, |change| powered(-change)),
+        above: first_flip(POWER_RANGE, POWER_NUDGE, powered),
+    };
+
+    // Each body is moved on its own, without its symmetry partners
+    let mut unlinked = level_data.clone();
+    unlinked.symmetry_groups.clear();
+
+    let moved = |ends: &[SelectedBody]| {
+        first_flip(MOVE_RANGE, MOVE_NUDGE, |distance| {
+            (0..8).any(|direction| {
+                let movement = Vec2F::new(distance, 0.0).rotated(f64::from(direction) * FRAC_PI_4);
+                let mut nudged = unlinked.clone();
+                for end in ends {
+                    nudged.move_selection(*end, movement);
+                }
+
+                flips(&nudged, launch)
+            })
+        })
+    };
+
+    Sensitivity {
+        launch,
+        cause,
+        angle,
+        power,
+        planets: (0..level_data.planets.len())
+            .map(|i| moved(&[SelectedBody::Planet(i)]))
+            .collect(),
+        walls: (0..level_data.walls.len())
+            .map(|i| {
+                moved(&[
+                    SelectedBody::Wall(i, WallEnd::Beginning),
+                    SelectedBody::Wall(i, WallEnd::End),
+                ])
+            })
+            .collect(),
+    }
+}
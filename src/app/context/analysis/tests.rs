@@ -0,0 +1,118 @@
+use super::{fly, sensitivity, LevelData, SimulationCause, Vec2F};
+use crate::app::context::{Integrator, Wall};
+
+/// A level with nothing but a wall running alongside a straight launch at the target, flown
+/// with swept collisions so grazing the wall counts
+fn corridor() -> LevelData {
+    let mut level_data = LevelData::default();
+    level_data.physics.parity = false;
+    level_data.planets.clear();
+    level_data.walls = vec![Wall::new(
+        Vec2F::new(100.0, 126.0),
+        Vec2F::new(200.0, 126.0),
+    )];
+
+    level_data
+}
+
+#[test]
+fn turning_the_launch_away_from_the_target_flips_it() {
+    let level_data = corridor();
+    let report = sensitivity(&level_data, Vec2F::new(2.0, 0.0));
+
+    assert_eq!(report.cause, SimulationCause::Target);
+
+    // The target is 20 across and 280 away, so the probe misses it a few degrees either way
+    let above = report
+        .angle
+        .above
+        .expect("turning clockwise misses the target");
+    let below = report
+        .angle
+        .below
+        .expect("turning anticlockwise misses the target");
+    assert!(
+        above > 1.0 && above < 5.0,
+        "flipped {above} degrees clockwise"
+    );
+    assert!(
+        below > 1.0 && below < 5.0,
+        "flipped {below} degrees anticlockwise"
+    );
+    assert!(
+        above < below,
+        "the wall below the path is closer than the target's edge"
+    );
+
+    // Without gravity, any power still reaches the target
+    assert_eq!(report.power.tightest(), None);
+}
+
+#[test]
+fn moving_a_wall_into_the_path_flips_it() {
+    let level_data = corridor();
+    let report = sensitivity(&level_data, Vec2F::new(2.0, 0.0));
+
+    let margin = report.walls[0].expect("the wall can be moved into the path");
+    assert!(margin <= 6.0, "flipped after moving {margin}");
+    assert_eq!(
+        report.tightest_move().map(|(body, _)| body),
+        Some(String::from("wall 0"))
+    );
+}
+
+#[test]
+fn the_intended_launch_is_saved() {
+    let mut level_data = corridor();
+    level_data.solution = Some(Vec2F::new(2.0, -0.5));
+
+    assert!(level_data.to_string().contains("#!solution 2 -0.5\n"));
+}
+
+#[test]
+fn power_is_only_scanned_within_the_launch_limits() {
+    // Short of time, a slow enough launch times out before reaching the target
+    let mut level_data = corridor();
+    level_data.limits.max_ticks = 200;
+    let report = sensitivity(&level_data, Vec2F::new(1.5, 0.0));
+
+    let below = report
+        .power
+        .below
+        .expect("a weaker launch runs out of time");
+    assert!(below < 0.5, "flipped {below} weaker");
+
+    // With more time, only launches weaker than the game allows would time out
+    level_data.limits.max_ticks = 300;
+    let report = sensitivity(&level_data, Vec2F::new(1.5, 0.0));
+
+    assert_eq!(report.power.below, None);
+}
+
+#[test]
+fn flights_follow_the_level_parity_setting() {
+    let mut level_data = LevelData::default();
+    let launch = Vec2F::new(2.0, -0.4);
+    let flight_end = |level_data: &LevelData| {
+        fly(level_data, launch, |simulation| simulation.ticks >= 100)
+            .0
+            .player
+            .pos
+    };
+
+    let mut ends = |parity| {
+        level_data.physics.parity = parity;
+        level_data.physics.integrator = Integrator::SemiImplicitEuler;
+        let euler = flight_end(&level_data);
+        level_data.physics.integrator = Integrator::RungeKutta4;
+
+        (euler, flight_end(&level_data))
+    };
+
+    // Parity flies the game's own physics whatever integrator the level asks for
+    let (euler, rk4) = ends(true);
+    assert_eq!(euler, rk4);
+
+    let (euler, rk4) = ends(false);
+    assert!((euler - rk4).magnitude() > 1e-6);
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use super::SimulationEvent;
+use super::{SimulationCause, SimulationEvent};
 
 #[derive(Debug, Clone, Copy)]
 pub enum AppState {
@@ -21,14 +21,28 @@ impl AppState {
 
 impl Display for AppState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let display_text = match *self {
-            Self::Editing => "Edit Mode",
-            Self::Aiming => "Aim Mode",
-            Self::Flying => "Simulating...",
-            Self::GameOver(SimulationEvent::Won) => "Target reached!",
-            Self::GameOver(SimulationEvent::Crashed) => "Probe crashed!",
-        };
+        match *self {
+            Self::Editing => write!(f, "Edit Mode"),
+            Self::Aiming => write!(f, "Aim Mode"),
+            Self::Flying => write!(f, "Simulating..."),
+            Self::GameOver(event) => {
+                match event.cause {
+                    SimulationCause::Target => write!(f, "Target reached!")?,
+                    SimulationCause::Planet(i) => write!(f, "Probe crashed into planet {i}!")?,
+                    SimulationCause::Wall(i) => write!(f, "Probe crashed into wall {i}!")?,
+                    SimulationCause::OutOfBounds => write!(f, "Probe left the level!")?,
+                    SimulationCause::Timeout => write!(f, "Flight timed out!")?,
+                    SimulationCause::Orbit(period) => {
+                        write!(f, "Probe is orbiting every {period} ticks!")?;
+                    }
+                }
 
-        write!(f, "{display_text}")
+                write!(
+                    f,
+                    " Tick {} at ({:.0}, {:.0})",
+                    event.tick, event.pos.x, event.pos.y
+                )
+            }
+        }
     }
 }
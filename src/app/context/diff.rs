@@ -0,0 +1,522 @@
+//! Comparing two versions of a level body by body, and merging the changes made to a level on two
+//! sides. The level format is a list of numbers, so text diffs and merges of it are meaningless
+
+use std::fmt::Display;
+use std::path::Path;
+use std::process::Command;
+
+use super::{position_at, Anchor, LevelData, Motion, Planet, Vec2F, Wall};
+
+#[cfg(test)]
+mod tests;
+
+/// How far a body can move between versions and still be matched up as the same body
+const MATCH_DISTANCE: f64 = 40.0;
+
+/// How one kind of body changed between two versions of a level
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BodyChanges {
+    /// Bodies in both versions, as their index in the old version and in the new one
+    pub matched: Vec<(usize, usize)>,
+    /// Indices in the old version of bodies that aren't in the new one
+    pub removed: Vec<usize>,
+    /// Indices in the new version of bodies that aren't in the old one
+    pub added: Vec<usize>,
+}
+
+impl BodyChanges {
+    /// Match up bodies between versions, closest pairs first. Bodies further apart than
+    /// [`MATCH_DISTANCE`] are never matched
+    fn new<T>(old: &[T], new: &[T], distance: fn(&T, &T) -> f64) -> Self {
+        let mut pairs: Vec<(f64, usize, usize)> = old
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| {
+                new.iter()
+                    .enumerate()
+                    .map(move |(j, b)| (distance(a, b), i, j))
+            })
+            .filter(|(apart, _, _)| *apart <= MATCH_DISTANCE)
+            .collect();
+        pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut old_matched = vec![false; old.len()];
+        let mut new_matched = vec![false; new.len()];
+        let mut matched = Vec::new();
+        for (_, i, j) in pairs {
+            if !old_matched[i] && !new_matched[j] {
+                old_matched[i] = true;
+                new_matched[j] = true;
+                matched.push((i, j));
+            }
+        }
+        matched.sort_unstable();
+
+        Self {
+            matched,
+            removed: (0..old.len()).filter(|i| !old_matched[*i]).collect(),
+            added: (0..new.len()).filter(|j| !new_matched[*j]).collect(),
+        }
+    }
+
+    /// Where the body at `old` in the old version is in the new one, if it's still there
+    fn new_index(&self, old: usize) -> Option<usize> {
+        self.matched
+            .iter()
+            .find(|(i, _)| *i == old)
+            .map(|(_, j)| *j)
+    }
+}
+
+/// How far apart two planets are
+pub fn planet_distance(a: &Planet, b: &Planet) -> f64 {
+    (a.pos - b.pos).magnitude()
+}
+
+/// How far apart two walls are: the furthest either end has to move, whichever way round they're
+/// drawn
+pub fn wall_distance(a: &Wall, b: &Wall) -> f64 {
+    let forwards = (a.pos1 - b.pos1)
+        .magnitude()
+        .max((a.pos2 - b.pos2).magnitude());
+    let backwards = (a.pos1 - b.pos2)
+        .magnitude()
+        .max((a.pos2 - b.pos1).magnitude());
+
+    forwards.min(backwards)
+}
+
+/// How a level changed from one version to another
+pub struct LevelDiff<'a> {
+    pub old: &'a LevelData,
+    pub new: &'a LevelData,
+    pub planets: BodyChanges,
+    pub walls: BodyChanges,
+    /// The names of everything else that changed
+    pub settings: Vec<&'static str>,
+}
+
+impl LevelDiff<'_> {
+    pub fn is_empty(&self) -> bool {
+        let unchanged = |changes: &BodyChanges, same: &dyn Fn(usize, usize) -> bool| {
+            changes.removed.is_empty()
+                && changes.added.is_empty()
+                && changes.matched.iter().all(|(i, j)| same(*i, *j))
+        };
+
+        unchanged(&self.planets, &|i, j| {
+            self.old.planets[i] == self.new.planets[j]
+        }) && unchanged(&self.walls, &|i, j| self.old.walls[i] == self.new.walls[j])
+            && self.settings.is_empty()
+    }
+
+    /// The planets matched between versions that have moved, as where they were and where they
+    /// are now
+    pub fn moved_planets(&self) -> impl Iterator<Item = (Vec2F, Vec2F)> + '_ {
+        self.planets.matched.iter().filter_map(|(i, j)| {
+            let (from, to) = (self.old.planets[*i].pos, self.new.planets[*j].pos);
+            (from != to).then_some((from, to))
+        })
+    }
+
+    /// The walls matched between versions that have moved, as where they were and where they are
+    /// now
+    pub fn moved_walls(&self) -> impl Iterator<Item = (&Wall, &Wall)> + '_ {
+        self.walls.matched.iter().filter_map(|(i, j)| {
+            let (from, to) = (&self.old.walls[*i], &self.new.walls[*j]);
+            (from.pos1 != to.pos1 || from.pos2 != to.pos2).then_some((from, to))
+        })
+    }
+}
+
+/// Compare two versions of a level
+pub fn diff<'a>(old: &'a LevelData, new: &'a LevelData) -> LevelDiff<'a> {
+    LevelDiff {
+        old,
+        new,
+        planets: BodyChanges::new(&old.planets, &new.planets, planet_distance),
+        walls: BodyChanges::new(&old.walls, &new.walls, wall_distance),
+        settings: changed_settings(old, new),
+    }
+}
+
+/// One line for each body removed (`-`), added (`+`), moved (`>`) or otherwise changed (`*`),
+/// with planet and wall numbers from the version they're in
+impl Display for LevelDiff<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (old, new) = (self.old, self.new);
+
+        for i in &self.planets.removed {
+            writeln!(f, "- planet {i}: {}", describe_planet(&old.planets[*i]))?;
+        }
+        for i in &self.walls.removed {
+            writeln!(f, "- wall {i}: {}", describe_wall(&old.walls[*i]))?;
+        }
+        for j in &self.planets.added {
+            writeln!(f, "+ planet {j}: {}", describe_planet(&new.planets[*j]))?;
+        }
+        for j in &self.walls.added {
+            writeln!(f, "+ wall {j}: {}", describe_wall(&new.walls[*j]))?;
+        }
+
+        for (i, j) in &self.planets.matched {
+            let (a, b) = (&old.planets[*i], &new.planets[*j]);
+            let moved = Planet {
+                pos: b.pos,
+                ..a.clone()
+            };
+
+            if a.pos != b.pos {
+                let also = if moved == *b { "" } else { ", and changed" };
+                writeln!(
+                    f,
+                    "> planet {i} -> {j}: moved from {} to {}{also}",
+                    a.pos, b.pos
+                )?;
+            } else if a != b {
+                writeln!(f, "* planet {i} -> {j}: {}", describe_planet(b))?;
+            }
+        }
+        for (i, j) in &self.walls.matched {
+            let (a, b) = (&old.walls[*i], &new.walls[*j]);
+
+            if a.pos1 != b.pos1 || a.pos2 != b.pos2 {
+                writeln!(f, "> wall {i} -> {j}: moved to {}", describe_wall(b))?;
+            } else if a != b {
+                writeln!(f, "* wall {i} -> {j}: changed")?;
+            }
+        }
+
+        if !self.settings.is_empty() {
+            writeln!(f, "* changed {}", self.settings.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn describe_planet(planet: &Planet) -> String {
+    format!("mass {} at {}", planet.signed_mass(), planet.pos)
+}
+
+fn describe_wall(wall: &Wall) -> String {
+    format!("from {} to {}", wall.pos1, wall.pos2)
+}
+
+/// The names of the parts of the level that aren't planets or walls which differ between versions
+fn changed_settings(old: &LevelData, new: &LevelData) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    let mut compare = |name: &'static str, same: bool| {
+        if !same {
+            changed.push(name);
+        }
+    };
+
+    compare("level position", old.level_position == new.level_position);
+    compare("player", old.player == new.player);
+    compare("target", old.target == new.target);
+    compare("wormholes", old.wormholes == new.wormholes);
+    compare("checkpoints", old.checkpoints == new.checkpoints);
+    compare(
+        "checkpoint order",
+        old.checkpoints_ordered == new.checkpoints_ordered,
+    );
+    compare("stars", old.stars == new.stars);
+    compare("zones", old.zones == new.zones);
+    compare("fuel", old.fuel.total_cmp(&new.fuel).is_eq());
+    compare("intended launch", old.solution == new.solution);
+    compare("physics", old.physics == new.physics);
+    compare("limits", old.limits == new.limits);
+    compare("substeps", old.substeps == new.substeps);
+    compare("symmetry", old.symmetry == new.symmetry);
+
+    changed
+}
+
+/// Another version of the level, to show what's changed since
+pub struct Comparison {
+    /// Where the other version came from
+    pub name: String,
+    pub level_data: LevelData,
+}
+
+impl Comparison {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(path);
+
+        Ok(Self {
+            name: String::from(name),
+            level_data: LevelData::load(path)?,
+        })
+    }
+
+    /// The version of the level at `path` in the last git commit
+    pub fn committed(path: &str) -> Result<Self, String> {
+        let path = Path::new(path);
+        let folder = path
+            .parent()
+            .filter(|folder| !folder.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return Err(String::from("Path is not valid unicode"));
+        };
+
+        let output = Command::new("git")
+            .arg("show")
+            .arg(format!("HEAD:./{name}"))
+            .current_dir(folder)
+            .output()
+            .map_err(|e| format!("Failed to run git: {e}"))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+        }
+
+        let text = String::from_utf8(output.stdout)
+            .map_err(|_| String::from("Committed level is not valid UTF-8"))?;
+        let name = format!("{name} at HEAD");
+
+        Ok(Self {
+            level_data: LevelData::parse(&text, &name)?,
+            name,
+        })
+    }
+}
+
+/// The result of merging the changes made to a level on two sides
+pub struct Merge {
+    pub level_data: LevelData,
+    /// Everything changed differently on each side. These are left as they are on our side
+    pub conflicts: Vec<String>,
+}
+
+/// Which bodies of one kind the merged level keeps
+struct BodyMerge<T> {
+    /// Our bodies, with any their side changed swapped for theirs, then the bodies only their
+    /// side added
+    bodies: Vec<T>,
+    /// Indices in `bodies` of bodies their side removed, which still have to be removed
+    removed: Vec<usize>,
+    /// Indices in `bodies` of bodies taken from their side, with their index there
+    taken: Vec<(usize, usize)>,
+    /// Where each of their bodies ended up in `bodies`, if it's there at all
+    theirs_index: Vec<Option<usize>>,
+}
+
+/// Merge one kind of body body by body: each side's changes to a body are kept as long as the
+/// other side left it alone
+fn merge_bodies<T: Clone + PartialEq>(
+    [base, ours, theirs]: [&[T]; 3],
+    distance: fn(&T, &T) -> f64,
+    describe: fn(&T) -> String,
+    conflicts: &mut Vec<String>,
+) -> BodyMerge<T> {
+    let our_changes = BodyChanges::new(base, ours, distance);
+    let their_changes = BodyChanges::new(base, theirs, distance);
+
+    let mut merge = BodyMerge {
+        bodies: ours.to_vec(),
+        removed: Vec::new(),
+        taken: Vec::new(),
+        theirs_index: vec![None; theirs.len()],
+    };
+
+    for (i, original) in base.iter().enumerate() {
+        match (our_changes.new_index(i), their_changes.new_index(i)) {
+            (Some(j), Some(k)) => {
+                merge.theirs_index[k] = Some(j);
+
+                if ours[j] == *original && theirs[k] != *original {
+                    merge.bodies[j] = theirs[k].clone();
+                    merge.taken.push((j, k));
+                } else if ours[j] != *original && theirs[k] != *original && ours[j] != theirs[k] {
+                    conflicts.push(format!("{} was changed on both sides", describe(original)));
+                }
+            }
+
+            (Some(j), None) => {
+                if ours[j] == *original {
+                    merge.removed.push(j);
+                } else {
+                    conflicts.push(format!(
+                        "{} was changed here but removed on their side",
+                        describe(original)
+                    ));
+                }
+            }
+
+            (None, Some(k)) => {
+                if theirs[k] != *original {
+                    conflicts.push(format!(
+                        "{} was removed here but changed on their side",
+                        describe(original)
+                    ));
+                }
+            }
+
+            (None, None) => (),
+        }
+    }
+
+    // Both sides adding the same body only adds it once
+    for k in their_changes.added {
+        if let Some(j) = our_changes.added.iter().find(|j| ours[**j] == theirs[k]) {
+            merge.theirs_index[k] = Some(*j);
+        } else {
+            merge.theirs_index[k] = Some(merge.bodies.len());
+            merge.taken.push((merge.bodies.len(), k));
+            merge.bodies.push(theirs[k].clone());
+        }
+    }
+
+    merge.removed.sort_unstable_by(|a, b| b.cmp(a));
+    merge
+}
+
+/// Keep whichever side changed a setting, or report a conflict if both changed it differently
+fn merge_setting<T: Clone + PartialEq>(
+    name: &str,
+    [base, ours, theirs]: [&T; 3],
+    conflicts: &mut Vec<String>,
+) -> T {
+    if ours == theirs || theirs == base {
+        ours.clone()
+    } else if ours == base {
+        theirs.clone()
+    } else {
+        conflicts.push(format!("The {name} was changed on both sides"));
+        ours.clone()
+    }
+}
+
+/// Merge the changes made to `base` on our side and their side. Planets and walls are matched up
+/// between versions by how close they are, so each can be merged on its own
+pub fn merge(base: &LevelData, ours: &LevelData, theirs: &LevelData) -> Merge {
+    let mut conflicts = Vec::new();
+    let mut merged = ours.clone();
+
+    merge_settings(&mut merged, [base, ours, theirs], &mut conflicts);
+
+    let planets = merge_bodies(
+        [&base.planets, &ours.planets, &theirs.planets],
+        planet_distance,
+        |planet| format!("The planet at {}", planet.pos),
+        &mut conflicts,
+    );
+    merged.planets = planets.bodies;
+
+    // Planets taken from their side orbit planets by their numbering on that side
+    for (j, _) in &planets.taken {
+        if let Motion::Orbit { anchor, .. } = &mut merged.planets[*j].motion {
+            if let Anchor::Planet(other) = *anchor {
+                *anchor = planets.theirs_index[other].map_or_else(
+                    || Anchor::Point(position_at(&theirs.planets, other, 0.0)),
+                    Anchor::Planet,
+                );
+            }
+        }
+    }
+    for j in planets.removed {
+        merged.remove_single_planet(j);
+    }
+
+    let walls = merge_bodies(
+        [&base.walls, &ours.walls, &theirs.walls],
+        wall_distance,
+        |wall| format!("The wall from {} to {}", wall.pos1, wall.pos2),
+        &mut conflicts,
+    );
+    merged.walls = walls.bodies;
+    for j in walls.removed {
+        merged.remove_single_wall(j);
+    }
+
+    Merge {
+        level_data: merged,
+        conflicts,
+    }
+}
+
+/// Merge everything in the level that isn't a planet or wall
+fn merge_settings(
+    merged: &mut LevelData,
+    [base, ours, theirs]: [&LevelData; 3],
+    conflicts: &mut Vec<String>,
+) {
+    merged.level_position = merge_setting(
+        "level position",
+        [
+            &base.level_position,
+            &ours.level_position,
+            &theirs.level_position,
+        ],
+        conflicts,
+    );
+    merged.player = merge_setting(
+        "player",
+        [&base.player, &ours.player, &theirs.player],
+        conflicts,
+    );
+    merged.target = merge_setting(
+        "target",
+        [&base.target, &ours.target, &theirs.target],
+        conflicts,
+    );
+    merged.wormholes = merge_setting(
+        "wormholes",
+        [&base.wormholes, &ours.wormholes, &theirs.wormholes],
+        conflicts,
+    );
+    merged.checkpoints = merge_setting(
+        "checkpoints",
+        [&base.checkpoints, &ours.checkpoints, &theirs.checkpoints],
+        conflicts,
+    );
+    merged.checkpoints_ordered = merge_setting(
+        "checkpoint order",
+        [
+            &base.checkpoints_ordered,
+            &ours.checkpoints_ordered,
+            &theirs.checkpoints_ordered,
+        ],
+        conflicts,
+    );
+    merged.stars = merge_setting(
+        "stars",
+        [&base.stars, &ours.stars, &theirs.stars],
+        conflicts,
+    );
+    merged.zones = merge_setting(
+        "zones",
+        [&base.zones, &ours.zones, &theirs.zones],
+        conflicts,
+    );
+    merged.fuel = merge_setting("fuel", [&base.fuel, &ours.fuel, &theirs.fuel], conflicts);
+    merged.solution = merge_setting(
+        "intended launch",
+        [&base.solution, &ours.solution, &theirs.solution],
+        conflicts,
+    );
+    merged.physics = merge_setting(
+        "physics",
+        [&base.physics, &ours.physics, &theirs.physics],
+        conflicts,
+    );
+    merged.limits = merge_setting(
+        "limits",
+        [&base.limits, &ours.limits, &theirs.limits],
+        conflicts,
+    );
+    merged.substeps = merge_setting(
+        "substeps",
+        [&base.substeps, &ours.substeps, &theirs.substeps],
+        conflicts,
+    );
+    merged.symmetry = merge_setting(
+        "symmetry",
+        [&base.symmetry, &ours.symmetry, &theirs.symmetry],
+        conflicts,
+    );
+}
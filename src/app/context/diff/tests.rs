@@ -0,0 +1,155 @@
+use super::{diff, merge, Anchor, Comparison, LevelData, Motion, Planet, Wall};
+use crate::app::context::Vec2F;
+
+fn level(planets: &[(f64, f64)]) -> LevelData {
+    LevelData {
+        planets: planets
+            .iter()
+            .map(|(x, y)| Planet::new(400.0, Vec2F::new(*x, *y)))
+            .collect(),
+        ..LevelData::default()
+    }
+}
+
+#[test]
+fn nearby_bodies_are_matched_as_moved() {
+    let old = level(&[(100.0, 100.0), (300.0, 100.0)]);
+    let mut new = level(&[(500.0, 100.0), (110.0, 95.0)]);
+    new.walls
+        .push(Wall::new(Vec2F::new(0.0, 0.0), Vec2F::new(50.0, 0.0)));
+
+    let diff = diff(&old, &new);
+    assert_eq!(diff.planets.matched, vec![(0, 1)]);
+    assert_eq!(diff.planets.removed, vec![1]);
+    assert_eq!(diff.planets.added, vec![0]);
+    assert_eq!(diff.walls.added, vec![0]);
+    assert!(diff.to_string().contains("> planet 0 -> 1: moved"));
+    assert!(!diff.is_empty());
+}
+
+#[test]
+fn reversed_walls_are_the_same_wall() {
+    let mut old = level(&[]);
+    old.walls
+        .push(Wall::new(Vec2F::new(0.0, 0.0), Vec2F::new(100.0, 0.0)));
+    let mut new = old.clone();
+    new.walls[0] = Wall::new(Vec2F::new(100.0, 0.0), Vec2F::new(0.0, 0.0));
+
+    assert_eq!(diff(&old, &new).walls.matched, vec![(0, 0)]);
+    assert!(diff(&old, &old).is_empty());
+}
+
+#[test]
+fn independent_changes_merge_cleanly() {
+    let base = level(&[(100.0, 100.0), (300.0, 100.0)]);
+
+    let mut ours = base.clone();
+    ours.planets[0].pos = Vec2F::new(105.0, 100.0);
+    ours.planets
+        .push(Planet::new(400.0, Vec2F::new(500.0, 500.0)));
+
+    // Their side removes the first planet, so the one they add orbits the second as planet 0
+    let mut theirs = base.clone();
+    theirs.planets.remove(0);
+    theirs.planets.push(Planet {
+        motion: Motion::Orbit {
+            anchor: Anchor::Planet(0),
+            radius: 50.0,
+            period: 100.0,
+            phase: 0.0,
+        },
+        ..Planet::new(100.0, Vec2F::new(350.0, 100.0))
+    });
+    theirs.fuel = 50.0;
+
+    let result = merge(&base, &ours, &theirs);
+    assert_eq!(
+        result.conflicts,
+        vec!["The planet at 100 100 was changed here but removed on their side"]
+    );
+
+    let merged = result.level_data;
+    assert_eq!(merged.planets.len(), 4);
+    assert!(matches!(
+        merged.planets[3].motion,
+        Motion::Orbit {
+            anchor: Anchor::Planet(1),
+            ..
+        }
+    ));
+    assert!((merged.fuel - 50.0).abs() < f64::EPSILON);
+
+    // Without our change to it, the first planet is removed
+    let mut ours = base.clone();
+    ours.planets
+        .push(Planet::new(400.0, Vec2F::new(500.0, 500.0)));
+
+    let result = merge(&base, &ours, &theirs);
+    assert_eq!(result.conflicts, Vec::<String>::new());
+    assert_eq!(result.level_data.planets.len(), 3);
+    assert_eq!(result.level_data.planets[0], base.planets[1]);
+    assert!(matches!(
+        result.level_data.planets[2].motion,
+        Motion::Orbit {
+            anchor: Anchor::Planet(0),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn changing_a_body_differently_on_both_sides_conflicts() {
+    let base = level(&[(100.0, 100.0)]);
+    let mut ours = base.clone();
+    ours.planets[0].pos = Vec2F::new(110.0, 100.0);
+    let mut theirs = base.clone();
+    theirs.planets[0].pos = Vec2F::new(90.0, 100.0);
+    theirs.fuel = 10.0;
+    ours.fuel = 20.0;
+
+    let result = merge(&base, &ours, &theirs);
+    assert_eq!(result.level_data.planets, ours.planets);
+    assert_eq!(result.conflicts.len(), 2);
+}
+
+#[test]
+fn only_bodies_that_moved_are_listed_as_moved() {
+    let mut old = level(&[(100.0, 100.0), (300.0, 100.0)]);
+    old.walls
+        .push(Wall::new(Vec2F::new(0.0, 0.0), Vec2F::new(100.0, 0.0)));
+    old.walls
+        .push(Wall::new(Vec2F::new(0.0, 200.0), Vec2F::new(100.0, 200.0)));
+
+    let mut new = old.clone();
+    new.planets[1].pos = Vec2F::new(310.0, 100.0);
+    new.walls[0].pos2 = Vec2F::new(100.0, 10.0);
+
+    let diff = diff(&old, &new);
+    let planets: Vec<_> = diff.moved_planets().collect();
+    let walls: Vec<_> = diff.moved_walls().collect();
+
+    assert_eq!(
+        planets,
+        vec![(Vec2F::new(300.0, 100.0), Vec2F::new(310.0, 100.0))]
+    );
+    assert_eq!(walls, vec![(&old.walls[0], &new.walls[0])]);
+}
+
+#[test]
+fn comparisons_load_the_saved_level() {
+    let level_data = level(&[(100.0, 100.0), (300.0, 150.0)]);
+    let path = std::env::temp_dir().join(format!("comparison-{}.obl", std::process::id()));
+    let path = path.to_str().expect("the temp dir is unicode");
+    level_data.save(path).expect("the level saves");
+
+    let comparison = Comparison::load(path);
+    std::fs::remove_file(path).expect("the level is removed");
+
+    let comparison = comparison.expect("the level loads");
+    assert_eq!(
+        comparison.name,
+        format!("comparison-{}.obl", std::process::id())
+    );
+    assert!(diff(&comparison.level_data, &level_data).is_empty());
+    assert!(Comparison::load("no/such/level.obl").is_err());
+}
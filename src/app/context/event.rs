@@ -1,15 +1,36 @@
+use std::f64::consts::PI;
+
 use sdl2::{
     event::Event,
     keyboard::{Keycode, Mod},
     mouse::MouseButton,
 };
 
-use super::{AppState, Planet, SelectedBody, Vec2F, Wall, WallEnd};
+use super::{
+    analysis, diff, replay, timeline, AppState, Burn, Checkpoint, Finding, Planet, PlanetKind,
+    Replay, ReplayInput, SelectedBody, Star, Vec2F, Wall, WallEnd, WallKind, Wormhole, Zone,
+    ZoneEffect,
+};
 
 /// Event methods
 impl super::Context {
     pub fn event(&mut self, event: &Event) {
+        // Leaving a flight early still saves it as a replay
+        if matches!(self.state, AppState::Flying)
+            && matches!(
+                event,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape | Keycode::R),
+                    ..
+                }
+            )
+        {
+            self.save_replay();
+        }
+
         match (self.state, event) {
+            (AppState::Editing, _) if self.history.is_some() => self.history_event(event),
+
             (
                 _,
                 Event::KeyDown {
@@ -26,6 +47,33 @@ impl super::Context {
                 },
             ) => self.show_background_image = !self.show_background_image,
 
+            (
+                _,
+                Event::KeyDown {
+                    keycode: Some(Keycode::E),
+                    ..
+                },
+            ) => self.show_energy_plot = !self.show_energy_plot,
+
+            (
+                _,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                },
+            ) => {
+                self.field_overlay = self.field_overlay.next();
+                println!("Gravity field overlay: {}", self.field_overlay);
+            }
+
+            (
+                AppState::Editing | AppState::Aiming,
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                },
+            ) => self.level_data.physics.parity = !self.level_data.physics.parity,
+
             (
                 _,
                 Event::KeyDown {
@@ -53,8 +101,69 @@ impl super::Context {
 
             (AppState::Aiming, _) => self.aim_event(event),
 
+            (AppState::Flying | AppState::GameOver(_), _) if self.playback.is_some() => {
+                self.playback_event(event);
+            }
+
             (AppState::Flying, _) => self.fly_event(event),
 
+            (AppState::GameOver(_), _) => self.timeline_event(event),
+        }
+    }
+
+    fn history_event(&mut self, event: &Event) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+
+        match event {
+            Event::KeyDown {
+                keycode: Some(Keycode::Up),
+                ..
+            } => history.select(-1),
+
+            Event::KeyDown {
+                keycode: Some(Keycode::Down),
+                ..
+            } => history.select(1),
+
+            Event::MouseWheel { y, .. } => history.select(-y),
+
+            Event::KeyDown {
+                keycode: Some(Keycode::Space),
+                ..
+            } => history.previewing = !history.previewing,
+
+            Event::KeyDown {
+                keycode: Some(Keycode::Return),
+                ..
+            } => {
+                if let Err(e) = self.restore_version() {
+                    eprintln!("Failed to restore version: {e}");
+                }
+            }
+
+            Event::KeyDown {
+                keycode: Some(Keycode::C),
+                ..
+            } => {
+                if let Some(version) = history.selected() {
+                    let comparison = diff::Comparison::load(&version.path);
+                    self.compare_with(comparison);
+                }
+            }
+
+            Event::KeyDown {
+                keymod,
+                keycode: Some(Keycode::X | Keycode::Delete),
+                ..
+            } => self.prune_versions(keymod.contains(Mod::LSHIFTMOD)),
+
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => self.history = None,
+
             _ => (),
         }
     }
@@ -97,16 +206,11 @@ impl super::Context {
                 keycode: Some(Keycode::D),
                 keymod,
                 ..
-            } if keymod.contains(Mod::LCTRLMOD) => {
-                if let SelectedBody::Planet(i) = self.edit_selection.body {
-                    self.level_data
-                        .planets
-                        .push(self.level_data.planets[i].clone());
-                }
-            }
+            } if keymod.contains(Mod::LCTRLMOD) => self.duplicate_selection(),
 
             Event::KeyDown {
                 keycode: Some(keycode),
+                keymod,
                 ..
             } => match keycode {
                 // Move selected body with arrow keys
@@ -118,46 +222,62 @@ impl super::Context {
                 }
 
                 Keycode::A | Keycode::N => {
-                    self.level_data
-                        .planets
-                        .push(Planet::new(400.0, self.edit_selection.last_mouse_pos));
+                    let i = self
+                        .level_data
+                        .spawn_planet(&Planet::new(400.0, self.edit_selection.last_mouse_pos));
 
-                    self.edit_selection.body =
-                        SelectedBody::Planet(self.level_data.planets.len() - 1);
+                    self.edit_selection.body = SelectedBody::Planet(i);
                 }
 
                 Keycode::W | Keycode::L => {
-                    self.level_data.walls.push(Wall::new(
+                    let i = self.level_data.spawn_wall(&Wall::new(
                         self.edit_selection.last_mouse_pos,
                         self.edit_selection.last_mouse_pos,
                     ));
 
-                    self.edit_selection.body =
-                        SelectedBody::Wall(self.level_data.walls.len() - 1, WallEnd::Beginning);
+                    self.edit_selection.body = SelectedBody::Wall(i, WallEnd::Beginning);
+                }
+
+                Keycode::T | Keycode::C | Keycode::K | Keycode::Z => {
+                    self.spawn_marker(*keycode, keymod.contains(Mod::LSHIFTMOD));
                 }
 
                 Keycode::H => self.edit_selection.toggle_grab_indicators(),
 
-                Keycode::D | Keycode::Backspace | Keycode::X => match self.edit_selection.body {
-                    SelectedBody::Planet(i) => {
-                        self.level_data.planets.remove(i);
+                Keycode::D | Keycode::Backspace | Keycode::X => self.remove_selection(),
 
-                        self.edit_selection.body = SelectedBody::None;
-                    }
+                Keycode::I | Keycode::U => {
+                    self.edit_kind(*keycode, keymod.contains(Mod::LSHIFTMOD));
+                }
 
-                    SelectedBody::Wall(i, _) => {
-                        self.level_data.walls.remove(i);
+                Keycode::V => self.watch_replay(),
 
-                        self.edit_selection.body = SelectedBody::None;
-                    }
+                Keycode::F => {
+                    let change = if keymod.contains(Mod::LSHIFTMOD) {
+                        -50.0
+                    } else {
+                        50.0
+                    };
+                    self.level_data.fuel = (self.level_data.fuel + change).max(0.0);
+                }
 
-                    _ => (),
-                },
+                // Set the centre of symmetry to the mouse
+                Keycode::Y if keymod.contains(Mod::LSHIFTMOD) => {
+                    self.level_data.symmetry.centre = self.edit_selection.last_mouse_pos;
+                }
 
-                Keycode::I => {
-                    if let SelectedBody::Planet(i) = self.edit_selection.body {
-                        self.level_data.planets[i].mass *= -1.0;
-                    }
+                Keycode::Y => {
+                    self.level_data.symmetry.mode = self.level_data.symmetry.mode.next();
+                }
+
+                Keycode::G if keymod.contains(Mod::LSHIFTMOD) => {
+                    self.level_data.physics.cycle_integrator();
+                }
+
+                Keycode::G => self.level_data.physics.cycle(),
+
+                Keycode::O | Keycode::LeftBracket | Keycode::RightBracket => {
+                    self.edit_motion(*keycode, keymod.contains(Mod::LSHIFTMOD));
                 }
 
                 _ => (),
@@ -167,6 +287,276 @@ impl super::Context {
         }
     }
 
+    /// Invert a held planet or flip a one-way wall. With shift, cycle what kind of planet a held
+    /// planet is, and with U, what kind of wall a held wall is
+    fn edit_kind(&mut self, keycode: Keycode, shift: bool) {
+        match (keycode, self.edit_selection.body) {
+            (Keycode::I, SelectedBody::Planet(i)) if shift => {
+                let planet = &mut self.level_data.planets[i];
+                planet.kind = planet.kind.next();
+            }
+
+            (Keycode::I, SelectedBody::Planet(i)) => {
+                let planet = &mut self.level_data.planets[i];
+                planet.kind = planet.kind.inverted();
+            }
+
+            // Flip which way a one-way wall lets the probe through
+            (Keycode::I, SelectedBody::Wall(i, end))
+                if self.level_data.walls[i].kind == WallKind::OneWay =>
+            {
+                let wall = &mut self.level_data.walls[i];
+                std::mem::swap(&mut wall.pos1, &mut wall.pos2);
+
+                // Keep holding the same point
+                let end = match end {
+                    WallEnd::Beginning => WallEnd::End,
+                    WallEnd::End => WallEnd::Beginning,
+                };
+                self.edit_selection.body = SelectedBody::Wall(i, end);
+            }
+
+            (Keycode::U, SelectedBody::Wall(i, _)) => {
+                let wall = &mut self.level_data.walls[i];
+                wall.kind = wall.kind.next();
+            }
+
+            _ => return,
+        }
+
+        self.level_data.sync_symmetry(self.edit_selection.body);
+    }
+
+    /// Edit the motion of the held planet or wall
+    fn edit_motion(&mut self, keycode: Keycode, shift: bool) {
+        match keycode {
+            Keycode::O => match self.edit_selection.body {
+                SelectedBody::Planet(i) => self.level_data.cycle_motion(i),
+                SelectedBody::Wall(i, _) | SelectedBody::WallHandle(i) if shift => {
+                    self.level_data.walls[i].motion.toggle_ping_pong();
+                }
+                SelectedBody::Wall(i, _) => self.level_data.cycle_wall_motion(i),
+                _ => (),
+            },
+
+            Keycode::LeftBracket | Keycode::RightBracket => {
+                let steps = if keycode == Keycode::LeftBracket {
+                    -1.0
+                } else {
+                    1.0
+                };
+
+                match self.edit_selection.body {
+                    SelectedBody::Planet(i)
+                        if shift
+                            && matches!(
+                                self.level_data.planets[i].kind,
+                                PlanetKind::BlackHole { .. }
+                            ) =>
+                    {
+                        self.level_data.planets[i].kind.change_horizon(steps);
+                    }
+                    SelectedBody::Planet(i) => self.level_data.change_period(i, steps * 25.0),
+                    SelectedBody::Wormhole(i, _) => {
+                        self.level_data.wormholes[i].rotation += steps * PI / 12.0;
+                    }
+                    SelectedBody::Zone(i) | SelectedBody::ZoneCorner(i, _) if shift => {
+                        self.level_data.zones[i].effect.rotate(steps);
+                    }
+                    SelectedBody::Zone(i) | SelectedBody::ZoneCorner(i, _) => {
+                        self.level_data.zones[i].effect.change_strength(steps);
+                    }
+                    SelectedBody::Wall(i, _) | SelectedBody::WallHandle(i) if shift => {
+                        self.level_data.walls[i].motion.change_sweep(steps);
+                    }
+                    SelectedBody::Wall(i, _) | SelectedBody::WallHandle(i) => {
+                        self.level_data.walls[i].motion.change_speed(steps);
+                    }
+                    _ => (),
+                }
+            }
+
+            _ => (),
+        }
+
+        self.level_data.sync_symmetry(self.edit_selection.body);
+    }
+
+    /// Spawn a wormhole, checkpoint, star or zone at the mouse. With shift, toggle ordered
+    /// checkpoints, search for a launch that collects every star or change a zone's effect instead
+    fn spawn_marker(&mut self, keycode: Keycode, shift: bool) {
+        match keycode {
+            Keycode::T => {
+                let pos = self.edit_selection.last_mouse_pos;
+                self.level_data
+                    .wormholes
+                    .push(Wormhole::new([pos, pos + Vec2F::new(60.0, 0.0)], 10.0));
+
+                self.edit_selection.body =
+                    SelectedBody::Wormhole(self.level_data.wormholes.len() - 1, 0);
+            }
+
+            Keycode::C if shift => {
+                self.level_data.checkpoints_ordered = !self.level_data.checkpoints_ordered;
+            }
+
+            Keycode::C => {
+                self.level_data
+                    .checkpoints
+                    .push(Checkpoint::new(self.edit_selection.last_mouse_pos, 12.0));
+
+                self.edit_selection.body =
+                    SelectedBody::Checkpoint(self.level_data.checkpoints.len() - 1);
+            }
+
+            Keycode::K if shift => self.search_for_stars(),
+
+            Keycode::K => {
+                self.level_data
+                    .stars
+                    .push(Star::new(self.edit_selection.last_mouse_pos));
+
+                self.edit_selection.body = SelectedBody::Star(self.level_data.stars.len() - 1);
+            }
+
+            Keycode::Z if shift => {
+                if let SelectedBody::Zone(i) | SelectedBody::ZoneCorner(i, _) =
+                    self.edit_selection.body
+                {
+                    let zone = &mut self.level_data.zones[i];
+                    zone.effect = zone.effect.next();
+                }
+            }
+
+            Keycode::Z => {
+                self.level_data.zones.push(Zone::rectangle(
+                    self.edit_selection.last_mouse_pos,
+                    Vec2F::new(80.0, 60.0),
+                    ZoneEffect::Wind(Vec2F::new(0.02, 0.0)),
+                ));
+
+                self.edit_selection.body = SelectedBody::Zone(self.level_data.zones.len() - 1);
+            }
+
+            _ => (),
+        }
+    }
+
+    /// Look for a launch that collects every star, and aim the probe with the best one found
+    fn search_for_stars(&mut self) {
+        self.start_task("Searching for stars", |level_data| {
+            Finding::Stars(analysis::collect_every_star(level_data))
+        });
+    }
+
+    /// Aim the probe with the best launch the star search found, as long as the level it
+    /// searched hasn't changed since
+    pub(super) fn found_stars(&mut self, search: analysis::StarSearch, level_hash: u64) {
+        if level_hash != replay::level_hash(&self.level_data) {
+            eprintln!("The level changed while searching for stars, search again with Shift+K");
+            return;
+        }
+
+        self.level_data.player.velocity = search.launch;
+
+        if search.collects_every_star() {
+            println!("Every star can be collected in one launch");
+        } else {
+            println!(
+                "No launch collects every star, at most {} of {}",
+                search.collected, search.total
+            );
+        }
+    }
+
+    fn duplicate_selection(&mut self) {
+        match self.edit_selection.body {
+            SelectedBody::Planet(i) => {
+                self.level_data.duplicate_planet(i);
+            }
+
+            SelectedBody::Star(i) => {
+                let star = self.level_data.stars[i].clone();
+                self.level_data.stars.push(star);
+            }
+
+            // Split the edge after the corner in two
+            SelectedBody::ZoneCorner(i, k) => {
+                let corners = &mut self.level_data.zones[i].corners;
+                let next = corners[(k + 1) % corners.len()];
+                corners.insert(k + 1, (corners[k] + next) / 2.0);
+
+                self.edit_selection.body = SelectedBody::ZoneCorner(i, k + 1);
+            }
+
+            SelectedBody::Waypoint(i, k) => {
+                if let Some(k) = self.level_data.duplicate_waypoint(i, k) {
+                    self.edit_selection.body = SelectedBody::Waypoint(i, k);
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    fn remove_selection(&mut self) {
+        match self.edit_selection.body {
+            SelectedBody::Planet(i) => {
+                self.level_data.remove_planet(i);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            SelectedBody::Wall(i, _) => {
+                self.level_data.remove_wall(i);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            SelectedBody::Waypoint(i, k) => {
+                self.level_data.remove_waypoint(i, k);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            SelectedBody::Wormhole(i, _) => {
+                self.level_data.wormholes.remove(i);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            SelectedBody::Checkpoint(i) => {
+                self.level_data.checkpoints.remove(i);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            SelectedBody::Star(i) => {
+                self.level_data.stars.remove(i);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            SelectedBody::Zone(i) => {
+                self.level_data.zones.remove(i);
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            // Zones need at least three corners
+            SelectedBody::ZoneCorner(i, k) => {
+                let corners = &mut self.level_data.zones[i].corners;
+                if corners.len() > 3 {
+                    corners.remove(k);
+                }
+
+                self.edit_selection.body = SelectedBody::None;
+            }
+
+            _ => (),
+        }
+    }
+
     fn aim_event(&mut self, event: &Event) {
         match event {
             Event::KeyDown {
@@ -179,20 +569,54 @@ impl super::Context {
             } => {
                 self.state = AppState::Flying;
                 self.simulation.push(&self.level_data);
+                self.recording = Some(Replay::new(&self.level_data));
+                self.timeline.launch(&self.simulation);
             }
 
             // Aim with the mouse
             Event::MouseMotion { x, y, .. } => {
-                self.level_data.player.aim_at(Vec2F::from_mouse_pos(*x, *y));
+                self.level_data
+                    .player
+                    .aim_at(Vec2F::from_mouse_pos(*x, *y), &self.level_data.physics);
             }
 
+            // Scrub through time to see where moving planets will be
+            Event::MouseWheel { y, .. } => self.scrub_aim_time(*y * 10),
+
+            // Plan a burn at the scrubbed tick with shift and the arrow keys, pin the last replay or
+            // analyse the intended launch
+            Event::KeyDown {
+                keycode: Some(code),
+                keymod,
+                ..
+            } if keymod.contains(Mod::LSHIFTMOD) => match code {
+                Keycode::Return => self.pin_ghost(true),
+                Keycode::M => self.analyse_sensitivity(),
+                _ => self.plan_burn(*code),
+            },
+
             // Aim with the arrow keys
             Event::KeyDown {
                 keycode: Some(code),
                 ..
             } => match code {
-                Keycode::Up => self.level_data.player.change_power(0.02),
-                Keycode::Down => self.level_data.player.change_power(-0.02),
+                Keycode::Return => self.pin_ghost(false),
+                Keycode::M => self.mark_solution(),
+                Keycode::Delete => self.clear_ghosts(),
+                Keycode::Backspace => {
+                    let time = self.aim_time;
+                    self.level_data.burns.retain(|burn| !burn.is_burning(time));
+                }
+                Keycode::Comma => self.scrub_aim_time(-1),
+                Keycode::Period => self.scrub_aim_time(1),
+                Keycode::Up => {
+                    let physics = self.level_data.physics;
+                    self.level_data.player.change_power(0.02, &physics);
+                }
+                Keycode::Down => {
+                    let physics = self.level_data.physics;
+                    self.level_data.player.change_power(-0.02, &physics);
+                }
                 Keycode::Left => self.level_data.player.change_angle(-1.0),
                 Keycode::Right => self.level_data.player.change_angle(1.0),
                 _ => (),
@@ -202,13 +626,80 @@ impl super::Context {
         }
     }
 
+    /// Add a burn in the direction of the arrow key at the scrubbed tick, or make the burn already
+    /// there last longer
+    fn plan_burn(&mut self, keycode: Keycode) {
+        const STEP: u32 = 5;
+
+        let Ok(direction) = Vec2F::try_from(&keycode) else {
+            return;
+        };
+        let tick = self.aim_time;
+
+        if let Some(burn) = self
+            .level_data
+            .burns
+            .iter_mut()
+            .find(|burn| burn.tick == tick && burn.direction == direction)
+        {
+            burn.duration += STEP;
+        } else {
+            self.level_data.burns.push(Burn::new(tick, STEP, direction));
+        }
+    }
+
+    fn scrub_aim_time(&mut self, change: i32) {
+        self.aim_time = self
+            .aim_time
+            .saturating_add_signed(change)
+            .min(self.level_data.limits.max_ticks);
+    }
+
+    /// Note something the player did in the flight being recorded
+    fn record(&mut self, input: ReplayInput) {
+        if let Some(recording) = &mut self.recording {
+            recording.inputs.push((self.simulation.ticks, input));
+        }
+    }
+
+    fn playback_event(&mut self, event: &Event) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Space => {
+                    playback.direction = i32::from(playback.direction == 0);
+                }
+                Keycode::Left => playback.direction = -1,
+                Keycode::Right => playback.direction = 1,
+                Keycode::Comma => playback.step(-1),
+                Keycode::Period => playback.step(1),
+                _ => (),
+            },
+
+            Event::MouseWheel { y, .. } => playback.step(*y * 10),
+
+            _ => (),
+        }
+    }
+
     fn fly_event(&mut self, event: &Event) {
         match event {
             Event::KeyDown {
                 keycode: Some(Keycode::Space),
                 ..
             } => {
-                self.simulation.playing = !self.simulation.playing;
+                if self.simulation.playing {
+                    self.simulation.playing = false;
+                    self.record(ReplayInput::Pause);
+                } else {
+                    self.resume_flight();
+                }
             }
 
             Event::KeyDown {
@@ -218,6 +709,75 @@ impl super::Context {
             } if !keymod.contains(Mod::LALTMOD) && (49..=52).contains(&(*keycode as i32)) => {
                 // Num1 to Num4
                 self.simulation.speed = *keycode as u32 - 48;
+                self.record(ReplayInput::Speed(self.simulation.speed));
+            }
+
+            // Thrust with the arrow keys, in both directions at once if two are held
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } => {
+                if let Ok(direction) = Vec2F::try_from(keycode) {
+                    let mut thrust = self.simulation.thrust();
+                    if direction.x == 0.0 {
+                        thrust.y = direction.y;
+                    } else {
+                        thrust.x = direction.x;
+                    }
+
+                    self.simulation.set_thrust(thrust);
+                }
+            }
+
+            Event::KeyUp {
+                keycode: Some(keycode),
+                ..
+            } => {
+                if let Ok(direction) = Vec2F::try_from(keycode) {
+                    let mut thrust = self.simulation.thrust();
+                    // Only let go if the key released is the one still being held
+                    if thrust.y * direction.y > 0.0 {
+                        thrust.y = 0.0;
+                    } else if thrust.x * direction.x > 0.0 {
+                        thrust.x = 0.0;
+                    }
+
+                    self.simulation.set_thrust(thrust);
+                }
+            }
+
+            _ => self.timeline_event(event),
+        }
+    }
+
+    /// Step or scrub through the flight with `,` `.`, the scroll wheel or by clicking the
+    /// timeline
+    fn timeline_event(&mut self, event: &Event) {
+        match event {
+            Event::KeyDown {
+                keycode: Some(Keycode::Comma),
+                ..
+            } => self.step_flight(-1),
+            Event::KeyDown {
+                keycode: Some(Keycode::Period),
+                ..
+            } => self.step_flight(1),
+
+            Event::MouseWheel { y, .. } => self.step_flight(*y * 10),
+
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => {
+                let mouse_pos = Vec2F::from_mouse_pos(*x, *y);
+                let length = self.timeline.length(&self.simulation);
+
+                if let Some(tick) = timeline::tick_at(mouse_pos, length) {
+                    self.step_flight(tick as i32 - self.simulation.ticks as i32);
+                }
             }
 
             _ => (),
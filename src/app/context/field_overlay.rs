@@ -0,0 +1,35 @@
+use std::fmt::Display;
+
+/// Which view of the planets' combined gravity is drawn over the level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldOverlay {
+    Off,
+    /// Arrows on a grid pointing the way the field pulls
+    Arrows,
+    /// Lines of equal potential energy
+    Contours,
+    /// The strength of the field, from blue where it's weak to yellow where it's strong
+    Heatmap,
+}
+
+impl FieldOverlay {
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Arrows,
+            Self::Arrows => Self::Contours,
+            Self::Contours => Self::Heatmap,
+            Self::Heatmap => Self::Off,
+        }
+    }
+}
+
+impl Display for FieldOverlay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Arrows => write!(f, "arrows"),
+            Self::Contours => write!(f, "contours"),
+            Self::Heatmap => write!(f, "heatmap"),
+        }
+    }
+}
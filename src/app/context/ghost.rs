@@ -0,0 +1,113 @@
+//! Launches pinned in Aim Mode, which are flown again against the level as it's edited so their
+//! paths can be compared
+
+use super::{replay, LevelData, Replay, SimulationCause, SimulationEvent, Vec2F};
+
+#[cfg(test)]
+mod tests;
+
+/// How far apart two paths have to be before they count as having diverged
+const DIVERGENCE: f64 = 1.0;
+
+/// A launch pinned to stay on screen
+pub struct Ghost {
+    pub name: String,
+    pub replay: Replay,
+    /// How the flight ended when it was pinned
+    pub pinned: SimulationCause,
+}
+
+/// Where a ghost flies through the level as it is now
+pub struct GhostFlight {
+    /// The probe's position at launch and after every tick
+    pub path: Vec<Vec2F>,
+    pub event: SimulationEvent,
+}
+
+/// Every ghost and the launch being aimed, flown against the level and kept until it changes
+pub struct Flights {
+    /// The [`replay::level_hash`] of the level the ghosts were flown against
+    level_hash: u64,
+    pub ghosts: Vec<GhostFlight>,
+    /// The launch being aimed, which changes without the level changing
+    launch: Replay,
+    pub live: GhostFlight,
+}
+
+impl Flights {
+    pub fn new(ghosts: &[Ghost], level_data: &LevelData) -> Self {
+        let launch = Replay::new(level_data);
+
+        Self {
+            level_hash: launch.level_hash,
+            ghosts: ghosts.iter().map(|ghost| ghost.fly(level_data)).collect(),
+            live: fly(&launch, level_data),
+            launch,
+        }
+    }
+
+    /// Fly again only what the level or the aim has changed since
+    pub fn update(&mut self, ghosts: &[Ghost], level_data: &LevelData) {
+        let launch = Replay::new(level_data);
+
+        if launch.level_hash != self.level_hash {
+            self.level_hash = launch.level_hash;
+            self.ghosts = ghosts.iter().map(|ghost| ghost.fly(level_data)).collect();
+        }
+
+        if launch != self.launch {
+            self.live = fly(&launch, level_data);
+            self.launch = launch;
+        }
+    }
+}
+
+impl Ghost {
+    /// Pin the launch in `replay`, named after the first letter no other ghost is using
+    pub fn new(replay: Replay, level_data: &LevelData, ghosts: &[Self]) -> Self {
+        let name = ('A'..='Z')
+            .map(String::from)
+            .find(|name| ghosts.iter().all(|ghost| ghost.name != *name))
+            .unwrap_or_else(|| (ghosts.len() + 1).to_string());
+
+        let pinned = fly(&replay, level_data).event.cause;
+
+        Self {
+            name,
+            replay,
+            pinned,
+        }
+    }
+
+    pub fn fly(&self, level_data: &LevelData) -> GhostFlight {
+        fly(&self.replay, level_data)
+    }
+}
+
+/// Fly a replay against the level, keeping the whole path
+pub fn fly(replay: &Replay, level_data: &LevelData) -> GhostFlight {
+    let mut simulation = replay.start(level_data);
+    let mut path = vec![simulation.player.pos];
+    let mut cursor = 0;
+
+    let event = loop {
+        replay::apply_inputs(&replay.inputs, &mut cursor, &mut simulation);
+        let event = simulation.tick();
+        path.push(simulation.player.pos);
+
+        if let Some(event) = event {
+            break event;
+        }
+    };
+
+    GhostFlight { path, event }
+}
+
+/// The first tick at which the two paths are further than [`DIVERGENCE`] apart, or where one of
+/// them ends before the other
+pub fn divergence(a: &[Vec2F], b: &[Vec2F]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(a, b)| (*a - *b).magnitude() > DIVERGENCE)
+        .or_else(|| (a.len() != b.len()).then(|| a.len().min(b.len()) - 1))
+}
@@ -0,0 +1,81 @@
+use super::{divergence, fly, Flights, Ghost, LevelData, Replay, Vec2F};
+
+/// An empty level, launched to the right
+fn level() -> LevelData {
+    let mut level_data = LevelData::default();
+    level_data.planets.clear();
+    level_data.player.velocity = Vec2F::new(2.0, 0.0);
+
+    level_data
+}
+
+#[test]
+fn ghosts_take_the_first_free_letter() {
+    let level_data = level();
+    let replay = Replay::new(&level_data);
+
+    let mut ghosts = vec![
+        Ghost::new(replay.clone(), &level_data, &[]),
+        Ghost::new(replay.clone(), &level_data, &[]),
+    ];
+    ghosts[0].name = String::from("B");
+    let ghost = Ghost::new(replay, &level_data, &ghosts);
+
+    assert_eq!(ghosts[1].name, "A");
+    assert_eq!(ghost.name, "C");
+}
+
+#[test]
+fn ghosts_diverge_where_the_level_changed() {
+    let mut level_data = level();
+    let ghost = Ghost::new(Replay::new(&level_data), &level_data, &[]);
+    let before = ghost.fly(&level_data);
+
+    let mut planet = LevelData::default().planets[0].clone();
+    planet.pos = Vec2F::new(
+        level_data.player.pos.x + 60.0,
+        level_data.player.pos.y + 40.0,
+    );
+    level_data.planets.push(planet);
+    let after = ghost.fly(&level_data);
+
+    let tick = divergence(&before.path, &after.path).expect("the planet bends the path");
+    assert!(tick > 0);
+    assert!((before.path[tick] - after.path[tick]).magnitude() > 1.0);
+    assert_eq!(divergence(&before.path, &before.path), None);
+}
+
+#[test]
+fn the_live_launch_matches_its_ghost() {
+    let level_data = level();
+    let replay = Replay::new(&level_data);
+
+    let ghost = Ghost::new(replay.clone(), &level_data, &[]);
+    let live = fly(&replay, &level_data);
+
+    assert_eq!(divergence(&ghost.fly(&level_data).path, &live.path), None);
+    assert_eq!(ghost.pinned, live.event.cause);
+}
+
+#[test]
+fn flights_are_only_flown_again_once_the_level_changes() {
+    let mut level_data = level();
+    let ghosts = vec![Ghost::new(Replay::new(&level_data), &level_data, &[])];
+    let mut flights = Flights::new(&ghosts, &level_data);
+    let pinned = flights.ghosts[0].path.clone();
+
+    // Aiming elsewhere only moves the live launch
+    level_data.player.velocity = Vec2F::new(0.0, 2.0);
+    flights.update(&ghosts, &level_data);
+    assert_eq!(flights.ghosts[0].path, pinned);
+    assert!(divergence(&flights.ghosts[0].path, &flights.live.path).is_some());
+
+    level_data.player.velocity = Vec2F::new(2.0, 0.0);
+    level_data.player.pos.y += 20.0;
+    flights.update(&ghosts, &level_data);
+    assert_ne!(flights.ghosts[0].path, pinned);
+    assert_eq!(
+        divergence(&flights.ghosts[0].path, &flights.live.path),
+        None
+    );
+}
@@ -3,9 +3,10 @@ use sdl2::event::Event;
 use sdl2::keyboard::{Keycode, Mod};
 
 use crate::app::Renderer;
+use super::diff::Comparison;
 use super::LevelData;
 
-mod incremental_path;
+pub(super) mod incremental_path;
 
 impl super::Context {
     pub fn global_keybinds(
@@ -30,25 +31,7 @@ impl super::Context {
             } if keymod.contains(Mod::LCTRLMOD | Mod::LSHIFTMOD)
                 || self.level_path == "new level" =>
             {
-                if self.level_path == "new_level" {
-                    self.level_path = "new_level.obl".into();
-                }
-                let level = FileDialog::new()
-                    .set_title("Save Level as")
-                    .add_filter("Spaceshipment Level", &["obl"])
-                    .set_directory("./levels/")
-                    .set_file_name(&self.level_path)
-                    .set_can_create_directories(true)
-                    .save_file();
-
-                if let Some(l) = level {
-                    let Some(path) = l.to_str() else {
-                        return Err(String::from("Path is not valid unicode"));
-                    };
-
-                    self.save(Some(String::from(path)))?;
-                    println!("Saved as {}", self.level_path);
-                }
+                self.save_as()?;
             }
 
             Event::KeyDown {
@@ -56,7 +39,7 @@ impl super::Context {
                 keycode: Some(Keycode::S),
                 ..
             } if keymod.contains(Mod::LCTRLMOD | Mod::LALTMOD) => {
-                self.save(Some(incremental_path::generate(&self.level_path)?))?;
+                self.save_incrementally()?;
                 println!("Saved incrementally to {}", self.level_path);
             }
 
@@ -76,6 +59,7 @@ impl super::Context {
             } if keymod.contains(Mod::LCTRLMOD) => {
                 self.level_path = String::from("new level");
                 self.level_data = LevelData::default();
+                self.forget_level();
                 println!("Opened new level");
             }
 
@@ -102,6 +86,24 @@ impl super::Context {
                 }
             }
 
+            Event::KeyDown {
+                keymod,
+                keycode: Some(keycode @ (Keycode::K | Keycode::G)),
+                ..
+            } if keymod.contains(Mod::LCTRLMOD) => self.compare_keybind(*keycode, *keymod)?,
+
+            Event::KeyDown {
+                keymod,
+                keycode: Some(Keycode::H),
+                ..
+            } if keymod.contains(Mod::LCTRLMOD) => self.toggle_history(),
+
+            Event::KeyDown {
+                keymod,
+                keycode: Some(Keycode::L),
+                ..
+            } if keymod.contains(Mod::LCTRLMOD) => self.lint_level(),
+
             Event::KeyDown {
                 keycode: Some(Keycode::F2),
                 ..
@@ -114,4 +116,63 @@ impl super::Context {
 
         Ok(true)
     }
+
+    /// Ask where to save the level, then save it there
+    fn save_as(&mut self) -> Result<(), String> {
+        if self.level_path == "new_level" {
+            self.level_path = "new_level.obl".into();
+        }
+        let level = FileDialog::new()
+            .set_title("Save Level as")
+            .add_filter("Spaceshipment Level", &["obl"])
+            .set_directory("./levels/")
+            .set_file_name(&self.level_path)
+            .set_can_create_directories(true)
+            .save_file();
+
+        if let Some(l) = level {
+            let Some(path) = l.to_str() else {
+                return Err(String::from("Path is not valid unicode"));
+            };
+
+            self.save(Some(String::from(path)))?;
+            println!("Saved as {}", self.level_path);
+        }
+
+        Ok(())
+    }
+
+    /// `Ctrl+K` compares with the saved level, or stops comparing. `Ctrl+Shift+K` picks a level
+    /// to compare with, `Ctrl+Alt+K` compares with the previous incremental save and `Ctrl+G`
+    /// with the last git commit
+    fn compare_keybind(&mut self, keycode: Keycode, keymod: Mod) -> Result<(), String> {
+        if keycode == Keycode::G {
+            self.compare_with(Comparison::committed(&self.level_path));
+        } else if keymod.contains(Mod::LSHIFTMOD) {
+            let level = FileDialog::new()
+                .set_title("Compare with Level")
+                .add_filter("Spaceshipment Level", &["obl"])
+                .set_directory("./levels/")
+                .set_file_name(&self.level_path)
+                .pick_file();
+
+            if let Some(l) = level {
+                let Some(path) = l.to_str() else {
+                    return Err(String::from("Path is not valid unicode"));
+                };
+
+                self.compare_with(Comparison::load(path));
+            }
+        } else if keymod.contains(Mod::LALTMOD) {
+            let previous = incremental_path::previous(&self.level_path)
+                .ok_or_else(|| String::from("This level has no earlier incremental save"));
+            self.compare_with(previous.and_then(|path| Comparison::load(&path)));
+        } else if self.comparison.take().is_some() {
+            println!("Stopped comparing");
+        } else {
+            self.compare_with(Comparison::load(&self.level_path));
+        }
+
+        Ok(())
+    }
 }
@@ -1,5 +1,8 @@
+use std::fs;
 use std::path::Path;
 
+use crate::app::context::LevelData;
+
 pub fn generate(old_path: &str) -> Result<String, String> {
     let mut reversed_filename: Vec<char> = old_path.trim_end_matches(".obl").chars().collect();
 
@@ -41,4 +44,79 @@ pub fn generate(old_path: &str) -> Result<String, String> {
     } else {
         Ok(new_level_path)
     }
-}
\ No newline at end of file
+}
+
+/// Split a level path into the path without its incremental number, and the number (0 for none)
+fn split(path: &str) -> (&str, u32) {
+    let stem = path.trim_end_matches(".obl");
+    let name = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+
+    (name, stem[name.len()..].parse().unwrap_or(0))
+}
+
+/// The name of the file at `path`, without its folder
+pub fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(path)
+        .to_owned()
+}
+
+/// The file the level at `path` was first saved as, before any incremental saves of it
+fn root(path: &str) -> String {
+    LevelData::load(path)
+        .ok()
+        .and_then(|level_data| level_data.lineage)
+        .unwrap_or_else(|| file_name(path))
+}
+
+/// Every incremental save of the level at `path`, and the level they were first saved from, oldest
+/// first with their incremental numbers. Levels that only happen to be numbered the same way, like
+/// `level-001.obl` and `level-002.obl`, aren't included unless one was saved from the other
+pub fn siblings(path: &str) -> Vec<(u32, String)> {
+    let lineage = root(path);
+    let stem = Path::new(split(path).0);
+    let Some(prefix) = stem.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    let folder = stem
+        .parent()
+        .filter(|folder| !folder.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+
+    let mut siblings: Vec<(u32, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let number = name.strip_prefix(prefix)?.strip_suffix(".obl")?;
+            if !number.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+
+            let sibling = stem.with_file_name(&name).to_str()?.to_owned();
+            Some((number.parse().unwrap_or(0), sibling))
+        })
+        .filter(|(_, sibling)| root(sibling) == lineage)
+        .collect();
+    siblings.sort();
+
+    siblings
+}
+
+/// The incremental save made before the level at `path`, if there is one
+pub fn previous(path: &str) -> Option<String> {
+    let number = split(path).1;
+
+    siblings(path)
+        .into_iter()
+        .filter(|(sibling, _)| *sibling < number)
+        .map(|(_, sibling)| sibling)
+        .next_back()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,78 @@
+use std::{fs, path::PathBuf};
+
+use super::{previous, siblings};
+use crate::app::context::LevelData;
+
+/// A fresh folder of levels, each saved from the level named alongside it, if any
+fn folder(name: &str, levels: &[(&str, Option<&str>)]) -> PathBuf {
+    let folder = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).expect("the folder is created");
+
+    for (level, lineage) in levels {
+        let level_data = LevelData {
+            lineage: lineage.map(String::from),
+            ..LevelData::default()
+        };
+        let path = folder.join(level);
+        level_data
+            .save(path.to_str().expect("the temp dir is unicode"))
+            .expect("the level saves");
+    }
+
+    folder
+}
+
+fn names(siblings: Vec<(u32, String)>) -> Vec<(u32, String)> {
+    siblings
+        .into_iter()
+        .map(|(number, path)| (number, super::file_name(&path)))
+        .collect()
+}
+
+#[test]
+fn only_saves_of_the_same_level_are_siblings() {
+    let folder = folder(
+        "incremental-siblings",
+        &[
+            ("mylevel.obl", None),
+            ("mylevel001.obl", Some("mylevel.obl")),
+            ("mylevel002.obl", Some("mylevel.obl")),
+            ("mylevel003.obl", None),
+            ("level-001.obl", None),
+            ("level-002.obl", None),
+        ],
+    );
+    let path = |level: &str| folder.join(level).to_str().expect("unicode").to_owned();
+
+    let saves = names(siblings(&path("mylevel002.obl")));
+    let unrelated = names(siblings(&path("level-002.obl")));
+    let previous = previous(&path("mylevel002.obl")).map(|path| super::file_name(&path));
+    fs::remove_dir_all(&folder).expect("the folder is removed");
+
+    assert_eq!(
+        saves,
+        vec![
+            (0, String::from("mylevel.obl")),
+            (1, String::from("mylevel001.obl")),
+            (2, String::from("mylevel002.obl")),
+        ]
+    );
+    assert_eq!(unrelated, vec![(2, String::from("level-002.obl"))]);
+    assert_eq!(previous.as_deref(), Some("mylevel001.obl"));
+}
+
+#[test]
+fn lineage_survives_saving_and_loading() {
+    let folder = folder(
+        "incremental-lineage",
+        &[("saved002.obl", Some("saved.obl"))],
+    );
+    let level_data = LevelData::load(folder.join("saved002.obl").to_str().expect("unicode"));
+    fs::remove_dir_all(&folder).expect("the folder is removed");
+
+    assert_eq!(
+        level_data.expect("the level loads").lineage.as_deref(),
+        Some("saved.obl")
+    );
+}
@@ -0,0 +1,121 @@
+//! Browsing the incremental saves of a level, made with `Ctrl+Alt+S`
+
+use std::time::{Duration, SystemTime};
+
+use super::global_keybinds::incremental_path;
+use super::LevelData;
+
+#[cfg(test)]
+mod tests;
+
+/// One saved version of a level: an incremental save, or the file they were first saved from
+pub struct Version {
+    /// The incremental number, which is 0 for the original file
+    pub number: u32,
+    pub path: String,
+    /// When the version was last saved, if the file system keeps track
+    pub modified: Option<SystemTime>,
+    /// The version's level, or why it couldn't be read
+    pub level_data: Result<LevelData, String>,
+}
+
+impl Version {
+    fn load(number: u32, path: String) -> Self {
+        Self {
+            number,
+            modified: std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            level_data: LevelData::load(&path),
+            path,
+        }
+    }
+
+    /// The number the version is listed under
+    pub fn label(&self) -> String {
+        if self.number == 0 {
+            String::from("original")
+        } else {
+            format!("{:0>3}", self.number)
+        }
+    }
+
+    /// How long ago the version was saved, as of `now`
+    pub fn age(&self, now: SystemTime) -> String {
+        self.modified
+            .and_then(|modified| now.duration_since(modified).ok())
+            .map_or_else(
+                || String::from("unknown"),
+                |age| format!("{} ago", short_duration(age)),
+            )
+    }
+}
+
+/// A duration to the nearest second, minute, hour or day, whichever is largest
+pub fn short_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m", seconds / 60),
+        3600..86400 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
+
+/// The saved versions of the level being edited, oldest first
+pub struct History {
+    pub versions: Vec<Version>,
+    /// The version highlighted in the panel
+    pub selected: usize,
+    /// Whether the highlighted version is drawn full size instead of the level
+    pub previewing: bool,
+}
+
+impl History {
+    /// Find every saved version of the level at `level_path`, highlighting that one
+    pub fn new(level_path: &str) -> Self {
+        let versions: Vec<Version> = incremental_path::siblings(level_path)
+            .into_iter()
+            .map(|(number, path)| Version::load(number, path))
+            .collect();
+        let selected = versions
+            .iter()
+            .position(|version| version.path == level_path)
+            .unwrap_or_else(|| versions.len().saturating_sub(1));
+
+        Self {
+            versions,
+            selected,
+            previewing: false,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Version> {
+        self.versions.get(self.selected)
+    }
+
+    /// The level to draw instead of the one being edited, while previewing a version
+    pub fn preview(&self) -> Option<&LevelData> {
+        self.selected()
+            .filter(|_| self.previewing)
+            .and_then(|version| version.level_data.as_ref().ok())
+    }
+
+    /// Move the highlight `change` versions later, or earlier if it's negative
+    pub fn select(&mut self, change: i32) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(change as isize)
+            .min(self.versions.len().saturating_sub(1));
+    }
+
+    /// The files of every version older than the highlighted one, except the level being edited
+    pub fn older(&self, level_path: &str) -> Vec<String> {
+        self.versions[..self.selected]
+            .iter()
+            .filter(|version| version.path != level_path)
+            .map(|version| version.path.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::{Duration, SystemTime};
+
+use super::{short_duration, History, Version};
+
+fn history(paths: &[&str]) -> History {
+    History {
+        versions: (0..)
+            .zip(paths)
+            .map(|(number, path)| Version {
+                number,
+                path: String::from(*path),
+                modified: None,
+                level_data: Err(String::from("Not loaded")),
+            })
+            .collect(),
+        selected: 0,
+        previewing: false,
+    }
+}
+
+#[test]
+fn ages_are_rounded_to_the_largest_unit() {
+    assert_eq!(short_duration(Duration::from_secs(59)), "59s");
+    assert_eq!(short_duration(Duration::from_secs(61)), "1m");
+    assert_eq!(short_duration(Duration::from_secs(7199)), "1h");
+    assert_eq!(short_duration(Duration::from_secs(3 * 86400 + 1)), "3d");
+
+    let version = Version {
+        modified: Some(SystemTime::UNIX_EPOCH),
+        ..history(&["a.obl"]).versions.remove(0)
+    };
+    assert_eq!(
+        version.age(SystemTime::UNIX_EPOCH + Duration::from_secs(330)),
+        "5m ago"
+    );
+    assert_eq!(version.label(), "original");
+}
+
+#[test]
+fn the_highlight_stays_on_a_version() {
+    let mut history = history(&["a.obl", "a002.obl", "a003.obl"]);
+    history.select(-1);
+    assert_eq!(history.selected, 0);
+    history.select(5);
+    assert_eq!(history.selected, 2);
+    assert_eq!(
+        history.selected().map(Version::label).as_deref(),
+        Some("002")
+    );
+}
+
+#[test]
+fn pruning_keeps_the_level_being_edited() {
+    let mut history = history(&["a.obl", "a002.obl", "a003.obl", "a004.obl"]);
+    history.selected = 3;
+
+    assert_eq!(history.older("a002.obl"), vec!["a.obl", "a003.obl"]);
+}
@@ -4,19 +4,49 @@ use std::{
     io::{BufWriter, Read, Write},
 };
 
-use super::{Planet, Player, SelectedBody, Selection, Target, Vec2F, Wall, WallEnd};
+use super::{
+    position_at, Anchor, Burn, Checkpoint, Limits, Motion, PhysicsProfile, Planet, PlanetKind,
+    Player, SelectedBody, Selection, Star, Target, Vec2F, Wall, WallEnd, WallKind, WallMotion,
+    Wormhole, Zone,
+};
+
+mod motion;
+mod symmetry;
+pub use symmetry::{LinkedBodies, Symmetry, SymmetryGroup, SymmetryMode};
 
 fn pop_or_eof(nums: &mut Vec<f64>) -> Result<f64, String> {
     nums.pop()
         .map_or_else(|| Err(String::from("Reached end of file early")), Ok)
 }
 
+#[derive(Clone)]
 pub struct LevelData {
     pub level_position: (i32, i32),
     pub player: Player,
     pub target: Target,
     pub planets: Vec<Planet>,
     pub walls: Vec<Wall>,
+    pub wormholes: Vec<Wormhole>,
+    /// Rings the probe has to fly through before the target counts
+    pub checkpoints: Vec<Checkpoint>,
+    pub checkpoints_ordered: bool,
+    pub stars: Vec<Star>,
+    pub zones: Vec<Zone>,
+    /// How many ticks the probe's engine can burn for. Levels without fuel can't thrust at all
+    pub fuel: f64,
+    /// Burns planned in Aim Mode. These aren't saved with the level
+    pub burns: Vec<Burn>,
+    /// The launch the level is designed to be solved with, if one has been marked
+    pub solution: Option<Vec2F>,
+    pub physics: PhysicsProfile,
+    pub limits: Limits,
+    /// How many steps each simulation tick is split into
+    pub substeps: u32,
+    /// The symmetry newly spawned bodies are created with
+    pub symmetry: Symmetry,
+    pub symmetry_groups: Vec<SymmetryGroup>,
+    /// The file this level was first saved as, if it's an incremental save of it
+    pub lineage: Option<String>,
 }
 
 impl LevelData {
@@ -26,8 +56,14 @@ impl LevelData {
         file.read_to_string(&mut text)
             .map_err(|_| String::from("File is not valid UTF-8"))?;
 
+        Self::parse(&text, filepath)
+    }
+
+    /// Read a level from the text of a level file. `name` says where the text came from in errors
+    pub fn parse(text: &str, name: &str) -> Result<Self, String> {
         let mut clean_text = String::new();
-        // Ignore comments
+        let mut metadata = Vec::new();
+        // Ignore comments, but keep metadata comments (starting with `#!`) for later
         let mut chars = text.chars();
         loop {
             let Some(c) = chars.next() else {
@@ -35,7 +71,11 @@ impl LevelData {
             };
 
             if c == '#' {
-                chars.find(|ch| *ch == '\n');
+                let comment: String = chars.by_ref().take_while(|ch| *ch != '\n').collect();
+
+                if let Some(line) = comment.strip_prefix('!') {
+                    metadata.push(String::from(line.trim()));
+                }
             } else {
                 clean_text.push(c);
             }
@@ -49,7 +89,7 @@ impl LevelData {
 
         let mut nums = Vec::new();
         for num in r_nums {
-            nums.push(num.map_err(|_| format!("Found non-number in {filepath}"))?);
+            nums.push(num.map_err(|_| format!("Found non-number in {name}"))?);
         }
 
         nums.reverse();
@@ -83,13 +123,110 @@ impl LevelData {
             ]));
         }
 
-        Ok(Self {
+        let mut level_data = Self {
             level_position,
             player,
             target,
             planets,
             walls,
-        })
+            wormholes: Vec::new(),
+            checkpoints: Vec::new(),
+            checkpoints_ordered: false,
+            stars: Vec::new(),
+            zones: Vec::new(),
+            fuel: 0.0,
+            burns: Vec::new(),
+            solution: None,
+            physics: PhysicsProfile::default(),
+            limits: Limits::default(),
+            substeps: 1,
+            symmetry: Symmetry::default(),
+            symmetry_groups: Vec::new(),
+            lineage: None,
+        };
+        level_data.read_extra_sections(&mut nums)?;
+
+        for line in &metadata {
+            level_data
+                .apply_metadata(line)
+                .map_err(|e| format!("Invalid metadata in {name}: {e}"))?;
+        }
+        level_data.refresh_orbits();
+
+        Ok(level_data)
+    }
+
+    /// Whether the level runs in parity mode without a `parity` line: only with the `game`
+    /// profile and no substeps
+    pub fn default_parity(&self) -> bool {
+        self.physics.default_parity() && self.substeps == 1
+    }
+
+    /// Read a single metadata line. Unknown keys are skipped so files from newer editors still open
+    fn apply_metadata(&mut self, line: &str) -> Result<(), String> {
+        let mut words = line.split_whitespace();
+
+        match words.next() {
+            Some("physics") => self.physics = PhysicsProfile::from_words(words)?,
+            Some("limits") => self.limits = Limits::from_words(words)?,
+            Some("substeps") => {
+                self.substeps = words
+                    .next()
+                    .and_then(|w| w.parse::<u32>().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| String::from("Invalid number of substeps"))?;
+                // Parity mode would ignore them
+                self.physics.parity &= self.substeps == 1;
+            }
+            Some("parity") => {
+                self.physics.parity = match words.next() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return Err(String::from("Parity must be `on` or `off`")),
+                };
+            }
+            Some("fuel") => {
+                self.fuel = words
+                    .next()
+                    .and_then(|w| w.parse::<f64>().ok())
+                    .filter(|fuel| *fuel >= 0.0)
+                    .ok_or_else(|| String::from("Invalid amount of fuel"))?;
+            }
+            Some("solution") => {
+                let mut number = || -> Result<f64, String> {
+                    words
+                        .next()
+                        .and_then(|w| w.parse::<f64>().ok())
+                        .ok_or_else(|| String::from("Invalid solution launch"))
+                };
+                self.solution = Some(Vec2F::new(number()?, number()?));
+            }
+            Some("checkpoints") => self.checkpoints_ordered = words.next() == Some("ordered"),
+            Some("lineage") => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                if name.is_empty() {
+                    return Err(String::from("Missing the level this was saved from"));
+                }
+                self.lineage = Some(name);
+            }
+            Some("symmetry") => self.symmetry = Symmetry::from_words(&mut words)?,
+            Some("mirror") => {
+                let group = SymmetryGroup::from_words(words)?;
+
+                let count = match group.bodies {
+                    LinkedBodies::Planets => self.planets.len(),
+                    LinkedBodies::Walls => self.walls.len(),
+                };
+                if group.members.iter().any(|(i, _)| *i >= count) {
+                    return Err(String::from("Symmetry link points to a missing body"));
+                }
+
+                self.symmetry_groups.push(group);
+            }
+            _ => (),
+        }
+
+        Ok(())
     }
 
     pub fn save(&self, filepath: &str) -> Result<(), String> {
@@ -104,12 +241,168 @@ impl LevelData {
     pub fn move_selection(&mut self, selected_body: SelectedBody, movement: Vec2F) {
         match selected_body {
             SelectedBody::Player => self.player.pos += movement,
-            SelectedBody::Planet(i) => self.planets[i].pos += movement,
+            SelectedBody::Planet(i) => {
+                let old_pos = self.planets[i].pos;
+                self.planets[i].pos += movement;
+                self.planet_moved(i, old_pos);
+            }
+            SelectedBody::OrbitCentre(i) => self.move_orbit_centre(i, movement),
+            SelectedBody::Waypoint(i, k) => self.move_waypoint(i, k, movement),
+            SelectedBody::WallHandle(i) => self.move_wall_handle(i, movement),
+            SelectedBody::Wormhole(i, end) => self.wormholes[i].ends[end] += movement,
+            SelectedBody::Checkpoint(i) => self.checkpoints[i].pos += movement,
+            SelectedBody::Star(i) => self.stars[i].pos += movement,
+            SelectedBody::Zone(i) => {
+                for corner in &mut self.zones[i].corners {
+                    *corner += movement;
+                }
+            }
+            SelectedBody::ZoneCorner(i, k) => self.zones[i].corners[k] += movement,
             SelectedBody::Target => self.target.pos += movement,
             SelectedBody::Wall(i, WallEnd::Beginning) => self.walls[i].pos1 += movement,
             SelectedBody::Wall(i, WallEnd::End) => self.walls[i].pos2 += movement,
             SelectedBody::None => (),
         };
+
+        self.sync_symmetry(selected_body);
+        self.refresh_orbits();
+    }
+
+    /// Read the sections added after the original format, in the order [`Self::extra_sections`]
+    /// writes them. They follow the walls, so older versions of the editor stop reading before
+    /// them. Each is a count followed by that many entries
+    fn read_extra_sections(&mut self, nums: &mut Vec<f64>) -> Result<(), String> {
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            let (i, mut motion) = Motion::from_nums(&mut || pop_or_eof(nums))?;
+
+            let planet = self
+                .planets
+                .get_mut(i)
+                .ok_or_else(|| String::from("Motion for a missing planet"))?;
+
+            if let Motion::Path { waypoints, .. } = &mut motion {
+                waypoints.insert(0, planet.pos);
+            }
+            planet.motion = motion;
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            let (i, motion) = WallMotion::from_nums(&mut || pop_or_eof(nums))?;
+
+            self.walls
+                .get_mut(i)
+                .ok_or_else(|| String::from("Motion for a missing wall"))?
+                .motion = motion;
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            let mut wormhole_nums = [0.0; 6];
+            for num in &mut wormhole_nums {
+                *num = pop_or_eof(nums)?;
+            }
+
+            self.wormholes.push(Wormhole::from_nums(&wormhole_nums));
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            self.checkpoints.push(Checkpoint::from_nums(&[
+                pop_or_eof(nums)?,
+                pop_or_eof(nums)?,
+                pop_or_eof(nums)?,
+            ]));
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            self.stars
+                .push(Star::new(Vec2F::new(pop_or_eof(nums)?, pop_or_eof(nums)?)));
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            self.zones.push(Zone::from_nums(&mut || pop_or_eof(nums))?);
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            let (i, kind) = WallKind::from_nums(&mut || pop_or_eof(nums))?;
+
+            self.walls
+                .get_mut(i)
+                .ok_or_else(|| String::from("Type for a missing wall"))?
+                .kind = kind;
+        }
+
+        for _ in 0..nums.pop().unwrap_or_default() as usize {
+            let (i, kind) = PlanetKind::from_nums(&mut || pop_or_eof(nums))?;
+
+            self.planets
+                .get_mut(i)
+                .ok_or_else(|| String::from("Type for a missing planet"))?
+                .kind = kind;
+        }
+
+        Ok(())
+    }
+
+    /// The sections written after the walls, in the order they're read
+    fn extra_sections(&self) -> Vec<Vec<String>> {
+        let motions = self
+            .planets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, planet)| planet.motion.to_nums(i))
+            .collect();
+
+        let wall_motions = self
+            .walls
+            .iter()
+            .enumerate()
+            .filter_map(|(i, wall)| wall.motion.to_nums(i))
+            .collect();
+
+        let wormholes = self
+            .wormholes
+            .iter()
+            .map(|wormhole| {
+                format!(
+                    "{} {} {} {}",
+                    wormhole.ends[0], wormhole.ends[1], wormhole.radius, wormhole.rotation
+                )
+            })
+            .collect();
+
+        let checkpoints = self
+            .checkpoints
+            .iter()
+            .map(|checkpoint| format!("{} {}", checkpoint.pos, checkpoint.radius))
+            .collect();
+
+        let stars = self.stars.iter().map(|star| star.pos.to_string()).collect();
+
+        let zones = self.zones.iter().map(Zone::to_nums).collect();
+
+        let wall_kinds = self
+            .walls
+            .iter()
+            .enumerate()
+            .filter_map(|(i, wall)| wall.kind.to_nums(i))
+            .collect();
+
+        let planet_kinds = self
+            .planets
+            .iter()
+            .enumerate()
+            .filter_map(|(i, planet)| planet.kind.to_nums(i))
+            .collect();
+
+        vec![
+            motions,
+            wall_motions,
+            wormholes,
+            checkpoints,
+            stars,
+            zones,
+            wall_kinds,
+            planet_kinds,
+        ]
     }
 
     pub fn resize_selection(&mut self, edit_selection: Selection, change: i32) {
@@ -117,7 +410,16 @@ impl LevelData {
 
         match edit_selection.body {
             SelectedBody::Target => self.target.change_size(change),
-            SelectedBody::Planet(i) => self.planets[i].change_size(change),
+            SelectedBody::Planet(i) => {
+                self.planets[i].change_size(change);
+                self.sync_symmetry(SelectedBody::Planet(i));
+            }
+            SelectedBody::Wormhole(i, _) => self.wormholes[i].change_size(change),
+            SelectedBody::Checkpoint(i) => self.checkpoints[i].change_size(change),
+            SelectedBody::Wall(i, _) => {
+                self.walls[i].kind.change_restitution(change);
+                self.sync_symmetry(edit_selection.body);
+            }
 
             SelectedBody::None => {
                 // Try target
@@ -133,8 +435,9 @@ impl LevelData {
                     let distance_to_planet =
                         (planet.pos - edit_selection.last_mouse_pos).magnitude();
 
-                    if distance_to_planet < planet.mass.abs() / 12.0 {
+                    if distance_to_planet < planet.radius() {
                         self.planets[i].change_size(change);
+                        self.sync_symmetry(SelectedBody::Planet(i));
                         break;
                     }
                 }
@@ -154,7 +457,7 @@ impl Display for LevelData {
 
         writeln!(f, "{}", self.planets.len())?;
         for planet in &self.planets {
-            writeln!(f, "{} {}", planet.mass.round(), planet.pos)?;
+            writeln!(f, "{} {}", planet.signed_mass().round(), planet.pos)?;
         }
 
         writeln!(f, "{}", self.walls.len())?;
@@ -162,6 +465,57 @@ impl Display for LevelData {
             writeln!(f, "{} {}", wall.pos1, wall.pos2)?;
         }
 
+        // Only write as many of the newer sections as needed, so simple levels stay readable by
+        // the game
+        let sections = self.extra_sections();
+        let used = sections
+            .iter()
+            .rposition(|s| !s.is_empty())
+            .map_or(0, |i| i + 1);
+        for section in &sections[..used] {
+            writeln!(f, "{}", section.len())?;
+            for entry in section {
+                writeln!(f, "{entry}")?;
+            }
+        }
+
+        // Metadata is stored in comments so older versions of the editor can still read the file
+        if self.physics.name() != Some("game") {
+            writeln!(f, "#!physics {}", self.physics)?;
+        }
+        if self.limits != Limits::default() {
+            writeln!(
+                f,
+                "#!limits {} {}",
+                self.limits.margin, self.limits.max_ticks
+            )?;
+        }
+        if self.substeps != 1 {
+            writeln!(f, "#!substeps {}", self.substeps)?;
+        }
+        if self.physics.parity != self.default_parity() {
+            let parity = if self.physics.parity { "on" } else { "off" };
+            writeln!(f, "#!parity {parity}")?;
+        }
+        if self.fuel > 0.0 {
+            writeln!(f, "#!fuel {}", self.fuel)?;
+        }
+        if let Some(solution) = self.solution {
+            writeln!(f, "#!solution {solution}")?;
+        }
+        if self.checkpoints_ordered {
+            writeln!(f, "#!checkpoints ordered")?;
+        }
+        if self.symmetry != Symmetry::default() {
+            writeln!(f, "#!symmetry {}", self.symmetry)?;
+        }
+        for group in &self.symmetry_groups {
+            writeln!(f, "#!mirror {group}")?;
+        }
+        if let Some(lineage) = &self.lineage {
+            writeln!(f, "#!lineage {lineage}")?;
+        }
+
         Ok(())
     }
 }
@@ -174,6 +528,20 @@ impl Default for LevelData {
             target: Target::new(20.0, Vec2F::new(330.0, 120.0)),
             planets: vec![Planet::new(400.0, Vec2F::new(200.0, 120.0))],
             walls: Vec::new(),
+            wormholes: Vec::new(),
+            checkpoints: Vec::new(),
+            checkpoints_ordered: false,
+            stars: Vec::new(),
+            zones: Vec::new(),
+            fuel: 0.0,
+            burns: Vec::new(),
+            solution: None,
+            physics: PhysicsProfile::default(),
+            limits: Limits::default(),
+            substeps: 1,
+            symmetry: Symmetry::default(),
+            symmetry_groups: Vec::new(),
+            lineage: None,
         }
     }
 }
@@ -0,0 +1,195 @@
+use super::{position_at, Anchor, LevelData, Motion, Vec2F, WallMotion};
+
+const DEFAULT_PERIOD: f64 = 300.0;
+const DEFAULT_RADIUS: f64 = 40.0;
+
+/// Planet motion methods
+impl LevelData {
+    /// Cycle a planet between standing still, orbiting a point, orbiting the nearest planet and
+    /// following a path
+    pub fn cycle_motion(&mut self, index: usize) {
+        let pos = self.planets[index].pos;
+        let period = self.planets[index]
+            .motion
+            .period()
+            .unwrap_or(DEFAULT_PERIOD);
+
+        let nearest_planet = (0..self.planets.len())
+            .filter(|i| *i != index)
+            .min_by(|a, b| {
+                let distance_a = (self.planets[*a].pos - pos).magnitude();
+                let distance_b = (self.planets[*b].pos - pos).magnitude();
+
+                distance_a.total_cmp(&distance_b)
+            });
+
+        self.planets[index].motion = match (&self.planets[index].motion, nearest_planet) {
+            (Motion::Static, _) => Motion::Orbit {
+                anchor: Anchor::Point(pos - Vec2F::new(DEFAULT_RADIUS, 0.0)),
+                radius: DEFAULT_RADIUS,
+                period,
+                phase: 0.0,
+            },
+
+            (
+                Motion::Orbit {
+                    anchor: Anchor::Point(_),
+                    ..
+                },
+                Some(other),
+            ) => {
+                let offset = pos - position_at(&self.planets, other, 0.0);
+
+                Motion::Orbit {
+                    anchor: Anchor::Planet(other),
+                    radius: offset.magnitude(),
+                    period,
+                    phase: offset.angle(),
+                }
+            }
+
+            (Motion::Orbit { .. }, _) => Motion::Path {
+                waypoints: vec![
+                    pos,
+                    pos + Vec2F::new(DEFAULT_RADIUS, 0.0),
+                    pos + Vec2F::new(DEFAULT_RADIUS, DEFAULT_RADIUS),
+                ],
+                period,
+            },
+
+            (Motion::Path { .. }, _) => Motion::Static,
+        };
+
+        self.refresh_orbits();
+    }
+
+    pub fn change_period(&mut self, index: usize, change: f64) {
+        let motion = &mut self.planets[index].motion;
+
+        if let Some(period) = motion.period() {
+            motion.set_period(period + change);
+        }
+    }
+
+    /// Keep a planet's motion in step after it was dragged away from `old_pos`, so that it still
+    /// starts wherever it was put
+    pub(super) fn planet_moved(&mut self, index: usize, old_pos: Vec2F) {
+        let pos = self.planets[index].pos;
+
+        let centre = match self.planets[index].motion {
+            Motion::Orbit {
+                anchor: Anchor::Point(centre),
+                ..
+            } => centre,
+            Motion::Orbit {
+                anchor: Anchor::Planet(other),
+                ..
+            } => position_at(&self.planets, other, 0.0),
+            Motion::Static | Motion::Path { .. } => Vec2F::ZERO,
+        };
+
+        match &mut self.planets[index].motion {
+            Motion::Static => (),
+
+            Motion::Orbit { radius, phase, .. } => {
+                let offset = pos - centre;
+                *radius = offset.magnitude();
+                *phase = offset.angle();
+            }
+
+            Motion::Path { waypoints, .. } => {
+                for waypoint in waypoints {
+                    *waypoint += pos - old_pos;
+                }
+            }
+        }
+    }
+
+    /// Move every orbiting planet to where its orbit starts, after anything it depends on moved
+    pub(super) fn refresh_orbits(&mut self) {
+        for i in 0..self.planets.len() {
+            if matches!(self.planets[i].motion, Motion::Orbit { .. }) {
+                self.planets[i].pos = position_at(&self.planets, i, 0.0);
+            }
+        }
+    }
+
+    pub fn move_orbit_centre(&mut self, index: usize, movement: Vec2F) {
+        if let Motion::Orbit {
+            anchor: Anchor::Point(centre),
+            ..
+        } = &mut self.planets[index].motion
+        {
+            *centre += movement;
+        }
+    }
+
+    /// Move a waypoint of a planet's path. The first waypoint is the planet itself
+    pub fn move_waypoint(&mut self, index: usize, waypoint: usize, movement: Vec2F) {
+        if let Motion::Path { waypoints, .. } = &mut self.planets[index].motion {
+            if let Some(point) = waypoints.get_mut(waypoint) {
+                *point += movement;
+            }
+        }
+    }
+
+    /// Add a copy of a waypoint right after it. Returns the index of the new waypoint
+    pub fn duplicate_waypoint(&mut self, index: usize, waypoint: usize) -> Option<usize> {
+        let Motion::Path { waypoints, .. } = &mut self.planets[index].motion else {
+            return None;
+        };
+
+        let copy = *waypoints.get(waypoint)? + Vec2F::new(10.0, 10.0);
+        waypoints.insert(waypoint + 1, copy);
+
+        Some(waypoint + 1)
+    }
+
+    /// Delete a waypoint. A path left with only the planet's own position stops moving
+    pub fn remove_waypoint(&mut self, index: usize, waypoint: usize) {
+        let motion = &mut self.planets[index].motion;
+
+        if let Motion::Path { waypoints, .. } = motion {
+            if waypoint > 0 && waypoint < waypoints.len() {
+                waypoints.remove(waypoint);
+            }
+
+            if waypoints.len() < 2 {
+                *motion = Motion::Static;
+            }
+        }
+    }
+
+    /// Stop planets from orbiting a planet that's about to be removed, and shift the indices
+    /// of the anchors after it. Orphaned orbits keep circling where the planet started
+    pub(super) fn unanchor(&mut self, removed: usize) {
+        let removed_pos = position_at(&self.planets, removed, 0.0);
+
+        for planet in &mut self.planets {
+            if let Motion::Orbit { anchor, .. } = &mut planet.motion {
+                match anchor {
+                    Anchor::Planet(i) if *i == removed => *anchor = Anchor::Point(removed_pos),
+                    Anchor::Planet(i) if *i > removed => *i -= 1,
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// Cycle a wall between standing still, sliding back and forth and rotating around its middle
+    pub fn cycle_wall_motion(&mut self, index: usize) {
+        let wall = &mut self.walls[index];
+
+        wall.motion = wall.motion.next((wall.pos1 + wall.pos2) / 2.0);
+    }
+
+    /// Move the handle of a wall's motion: where a sliding wall slides to, or the pivot of a
+    /// rotating one
+    pub fn move_wall_handle(&mut self, index: usize, movement: Vec2F) {
+        match &mut self.walls[index].motion {
+            WallMotion::Static => (),
+            WallMotion::Slide { offset, .. } => *offset += movement,
+            WallMotion::Rotate { pivot, .. } => *pivot += movement,
+        }
+    }
+}
@@ -0,0 +1,570 @@
+use std::{f64::consts::TAU, fmt::Display, str::FromStr};
+
+use super::{Anchor, LevelData, Motion, Planet, SelectedBody, Vec2F, Wall, WallMotion};
+
+#[cfg(test)]
+mod tests;
+
+/// The most copies rotational symmetry can make. Levels asking for more are refused, rather than
+/// spawning a copy of every body for each
+pub const MAX_ROTATIONAL_ORDER: u32 = 12;
+
+/// How close two copies of a body have to be to count as the same, such as a body spawned on a
+/// mirror line and its reflection
+const SAME_PLACE: f64 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymmetryMode {
+    Off,
+    /// Mirrored left to right across the vertical line through the centre
+    Horizontal,
+    /// Mirrored top to bottom across the horizontal line through the centre
+    Vertical,
+    Both,
+    /// N-fold rotational symmetry around the centre
+    Rotational(u32),
+}
+
+impl SymmetryMode {
+    /// Cycle to the next symmetry mode. Rotational symmetry goes up to 6-fold
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Horizontal,
+            Self::Horizontal => Self::Vertical,
+            Self::Vertical => Self::Both,
+            Self::Both => Self::Rotational(2),
+            Self::Rotational(n) if n < 6 => Self::Rotational(n + 1),
+            Self::Rotational(_) => Self::Off,
+        }
+    }
+}
+
+impl Display for SymmetryMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Horizontal => write!(f, "horizontal"),
+            Self::Vertical => write!(f, "vertical"),
+            Self::Both => write!(f, "both"),
+            Self::Rotational(n) => write!(f, "rotational:{n}"),
+        }
+    }
+}
+
+impl FromStr for SymmetryMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "horizontal" => Ok(Self::Horizontal),
+            "vertical" => Ok(Self::Vertical),
+            "both" => Ok(Self::Both),
+            _ => s
+                .strip_prefix("rotational:")
+                .and_then(|n| n.parse::<u32>().ok())
+                .filter(|n| (2..=MAX_ROTATIONAL_ORDER).contains(n))
+                .map(Self::Rotational)
+                .ok_or_else(|| format!("Unknown symmetry mode `{s}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    pub centre: Vec2F,
+}
+
+impl Symmetry {
+    pub const fn new(mode: SymmetryMode, centre: Vec2F) -> Self {
+        Self { mode, centre }
+    }
+
+    /// The number of copies of a body (including the original) this symmetry creates
+    pub const fn order(self) -> usize {
+        match self.mode {
+            SymmetryMode::Off => 1,
+            SymmetryMode::Horizontal | SymmetryMode::Vertical => 2,
+            SymmetryMode::Both => 4,
+            SymmetryMode::Rotational(n) => n as usize,
+        }
+    }
+
+    /// Map `pos` onto the copy with the given transform index. Transform 0 is always the identity
+    pub fn apply(self, transform: usize, pos: Vec2F) -> Vec2F {
+        let offset = pos - self.centre;
+
+        let mirrored = match (self.mode, transform) {
+            (_, 0) | (SymmetryMode::Off, _) => offset,
+            (SymmetryMode::Horizontal | SymmetryMode::Both, 1) => Vec2F::new(-offset.x, offset.y),
+            (SymmetryMode::Vertical, 1) | (SymmetryMode::Both, 2) => {
+                Vec2F::new(offset.x, -offset.y)
+            }
+            (SymmetryMode::Both, _) => offset * -1.0,
+            (SymmetryMode::Rotational(n), k) => rotate(offset, TAU * k as f64 / n as f64),
+            (SymmetryMode::Horizontal | SymmetryMode::Vertical, _) => offset,
+        };
+
+        self.centre + mirrored
+    }
+
+    /// Whether the copy with the given transform index is a reflection, which turns the other way
+    /// to the original
+    pub const fn reflects(self, transform: usize) -> bool {
+        match self.mode {
+            SymmetryMode::Horizontal | SymmetryMode::Vertical => transform == 1,
+            SymmetryMode::Both => transform == 1 || transform == 2,
+            SymmetryMode::Off | SymmetryMode::Rotational(_) => false,
+        }
+    }
+
+    /// Map a position on the copy with transform `from` onto the copy with transform `to`
+    fn map(self, from: usize, to: usize, pos: Vec2F) -> Vec2F {
+        self.apply(to, self.invert(from, pos))
+    }
+
+    /// Undo [`Symmetry::apply`], mapping a position on a copy back onto the original
+    pub fn invert(self, transform: usize, pos: Vec2F) -> Vec2F {
+        match self.mode {
+            SymmetryMode::Rotational(n) => {
+                self.centre + rotate(pos - self.centre, -TAU * transform as f64 / n as f64)
+            }
+            // Every mirror is its own inverse
+            _ => self.apply(transform, pos),
+        }
+    }
+
+    /// Parse the mode and centre of a symmetry from metadata arguments
+    pub fn from_words<'a>(words: &mut impl Iterator<Item = &'a str>) -> Result<Self, String> {
+        let mode = words
+            .next()
+            .ok_or_else(|| String::from("Missing symmetry mode"))?
+            .parse()?;
+
+        let mut coord = || {
+            words
+                .next()
+                .and_then(|w| w.parse::<f64>().ok())
+                .ok_or_else(|| String::from("Missing symmetry centre"))
+        };
+        let centre = Vec2F::new(coord()?, coord()?);
+
+        Ok(Self::new(mode, centre))
+    }
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Self::new(SymmetryMode::Off, Vec2F::new(200.0, 120.0))
+    }
+}
+
+/// A wall's motion on the copy with transform `from`, mapped onto the copy with transform `to`.
+/// Reflected walls turn the other way
+fn mirrored_wall_motion(
+    motion: WallMotion,
+    symmetry: Symmetry,
+    from: usize,
+    to: usize,
+) -> WallMotion {
+    match motion {
+        WallMotion::Static => WallMotion::Static,
+
+        WallMotion::Slide {
+            offset,
+            speed,
+            ping_pong,
+        } => WallMotion::Slide {
+            offset: symmetry.map(from, to, symmetry.centre + offset) - symmetry.centre,
+            speed,
+            ping_pong,
+        },
+
+        WallMotion::Rotate {
+            pivot,
+            speed,
+            sweep,
+            ping_pong,
+        } => WallMotion::Rotate {
+            pivot: symmetry.map(from, to, pivot),
+            speed: if symmetry.reflects(from) == symmetry.reflects(to) {
+                speed
+            } else {
+                -speed
+            },
+            sweep,
+            ping_pong,
+        },
+    }
+}
+
+fn rotate(v: Vec2F, angle: f64) -> Vec2F {
+    let (sin, cos) = angle.sin_cos();
+
+    Vec2F::new(v.x.mul_add(cos, -v.y * sin), v.x.mul_add(sin, v.y * cos))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkedBodies {
+    Planets,
+    Walls,
+}
+
+/// A set of bodies that are copies of each other under a symmetry. Editing any one of them
+/// updates the rest
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetryGroup {
+    pub symmetry: Symmetry,
+    pub bodies: LinkedBodies,
+    /// Pairs of (body index, transform index)
+    pub members: Vec<(usize, usize)>,
+}
+
+impl SymmetryGroup {
+    fn transform_of(&self, bodies: LinkedBodies, index: usize) -> Option<usize> {
+        if self.bodies != bodies {
+            return None;
+        }
+
+        self.members
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, transform)| *transform)
+    }
+
+    /// Parse the arguments of a `mirror` metadata line
+    pub fn from_words<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Self, String> {
+        let bodies = match words.next() {
+            Some("planet") => LinkedBodies::Planets,
+            Some("wall") => LinkedBodies::Walls,
+            _ => return Err(String::from("Symmetry link has no body type")),
+        };
+
+        let symmetry = Symmetry::from_words(&mut words)?;
+
+        let mut members = Vec::new();
+        for word in words {
+            let member = word
+                .split_once(':')
+                .and_then(|(i, t)| Some((i.parse().ok()?, t.parse().ok()?)))
+                .ok_or_else(|| format!("Invalid symmetry link `{word}`"))?;
+
+            members.push(member);
+        }
+
+        Ok(Self {
+            symmetry,
+            bodies,
+            members,
+        })
+    }
+}
+
+impl Display for SymmetryGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bodies = match self.bodies {
+            LinkedBodies::Planets => "planet",
+            LinkedBodies::Walls => "wall",
+        };
+        write!(f, "{bodies} {}", self.symmetry)?;
+
+        for (index, transform) in &self.members {
+            write!(f, " {index}:{transform}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Symmetry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.mode, self.centre)
+    }
+}
+
+/// Symmetry methods
+impl LevelData {
+    /// Spawn a planet along with its mirrored partners, returning the index of the original.
+    /// Copies that would land on top of another, like those of a planet on a mirror line, are
+    /// left out
+    pub fn spawn_planet(&mut self, planet: &Planet) -> usize {
+        let first = self.planets.len();
+        let symmetry = self.symmetry;
+        let mut transforms = Vec::new();
+
+        for transform in 0..symmetry.order() {
+            let pos = symmetry.apply(transform, planet.pos);
+            if self.planets[first..]
+                .iter()
+                .any(|copy| (copy.pos - pos).magnitude() < SAME_PLACE)
+            {
+                continue;
+            }
+
+            let mut copy = planet.clone();
+            copy.pos = pos;
+            self.planets.push(copy);
+            transforms.push(transform);
+        }
+
+        self.link(LinkedBodies::Planets, first, &transforms);
+
+        first
+    }
+
+    /// Spawn a wall along with its mirrored partners, returning the index of the original. Copies
+    /// that would land on top of another are left out, whichever way round they're drawn
+    pub fn spawn_wall(&mut self, wall: &Wall) -> usize {
+        let first = self.walls.len();
+        let symmetry = self.symmetry;
+        let mut transforms = Vec::new();
+
+        for transform in 0..symmetry.order() {
+            let mut copy = wall.clone();
+            copy.pos1 = symmetry.apply(transform, wall.pos1);
+            copy.pos2 = symmetry.apply(transform, wall.pos2);
+
+            let same = |a: Vec2F, b: Vec2F| (a - b).magnitude() < SAME_PLACE;
+            if self.walls[first..].iter().any(|other| {
+                (same(other.pos1, copy.pos1) && same(other.pos2, copy.pos2))
+                    || (same(other.pos1, copy.pos2) && same(other.pos2, copy.pos1))
+            }) {
+                continue;
+            }
+
+            self.walls.push(copy);
+            transforms.push(transform);
+        }
+
+        self.link(LinkedBodies::Walls, first, &transforms);
+
+        first
+    }
+
+    /// Link the bodies from `first` to the end of the list as copies under the current symmetry,
+    /// with the given transforms
+    /// Duplicate a planet along with its mirrored partners, linking the copies into a new group
+    /// like the originals. Returns the index of the copy of the planet itself
+    pub fn duplicate_planet(&mut self, i: usize) -> usize {
+        let Some((g, _, _)) = self.group_of(SelectedBody::Planet(i)) else {
+            self.planets.push(self.planets[i].clone());
+            return self.planets.len() - 1;
+        };
+
+        let group = self.symmetry_groups[g].clone();
+        let first = self.planets.len();
+        let mut copy = first;
+
+        for (k, (member, _)) in group.members.iter().enumerate() {
+            self.planets.push(self.planets[*member].clone());
+            if *member == i {
+                copy = first + k;
+            }
+        }
+
+        self.symmetry_groups.push(SymmetryGroup {
+            members: (first..)
+                .zip(group.members.iter().map(|(_, transform)| *transform))
+                .collect(),
+            ..group
+        });
+
+        copy
+    }
+
+    fn link(&mut self, bodies: LinkedBodies, first: usize, transforms: &[usize]) {
+        if transforms.len() < 2 {
+            return;
+        }
+
+        self.symmetry_groups.push(SymmetryGroup {
+            symmetry: self.symmetry,
+            bodies,
+            members: (first..).zip(transforms.iter().copied()).collect(),
+        });
+    }
+
+    /// Find the group a selected body belongs to, along with the index of the linked body and its
+    /// transform in the group
+    fn group_of(&self, body: SelectedBody) -> Option<(usize, usize, usize)> {
+        let (bodies, index) = match body {
+            SelectedBody::Planet(i)
+            | SelectedBody::OrbitCentre(i)
+            | SelectedBody::Waypoint(i, _) => (LinkedBodies::Planets, i),
+            SelectedBody::Wall(i, _) | SelectedBody::WallHandle(i) => (LinkedBodies::Walls, i),
+            _ => return None,
+        };
+
+        self.symmetry_groups
+            .iter()
+            .enumerate()
+            .find_map(|(g, group)| Some((g, index, group.transform_of(bodies, index)?)))
+    }
+
+    /// Copy the state of the selected body onto all of its mirrored partners, including how it
+    /// moves. A reflected copy of an orbit still goes round the same way, since orbits can't be
+    /// turned around, but it keeps the same period so the copies stay in step
+    pub fn sync_symmetry(&mut self, body: SelectedBody) {
+        let Some((g, i, transform)) = self.group_of(body) else {
+            return;
+        };
+        let group = self.symmetry_groups[g].clone();
+        let symmetry = group.symmetry;
+
+        match group.bodies {
+            LinkedBodies::Planets => {
+                let source = self.planets[i].clone();
+                let origin = symmetry.invert(transform, source.pos);
+
+                for (j, t) in group.members.iter().copied() {
+                    let mut old_pos = self.planets[j].pos;
+                    self.planets[j].mass = source.mass;
+                    self.planets[j].kind = source.kind;
+                    self.planets[j].pos = symmetry.apply(t, origin);
+
+                    if j != i {
+                        self.planets[j].motion = self.mirrored_motion(&group, transform, t);
+                        // The copied motion already starts in the right place
+                        old_pos = self.planets[j].pos;
+                    }
+                    self.planet_moved(j, old_pos);
+                }
+            }
+
+            LinkedBodies::Walls => {
+                let source = self.walls[i].clone();
+                let origin1 = symmetry.invert(transform, source.pos1);
+                let origin2 = symmetry.invert(transform, source.pos2);
+
+                for (j, t) in group.members {
+                    self.walls[j].pos1 = symmetry.apply(t, origin1);
+                    self.walls[j].pos2 = symmetry.apply(t, origin2);
+                    self.walls[j].kind = source.kind;
+                    self.walls[j].motion =
+                        mirrored_wall_motion(source.motion, symmetry, transform, t);
+                }
+            }
+        }
+    }
+
+    /// The motion of the planet with transform `from` in a group, mapped onto the copy with
+    /// transform `to`. Anchors on other linked planets are swapped for the matching partner
+    fn mirrored_motion(&self, group: &SymmetryGroup, from: usize, to: usize) -> Motion {
+        let symmetry = group.symmetry;
+        let source = group
+            .members
+            .iter()
+            .find(|(_, t)| *t == from)
+            .map(|(i, _)| &self.planets[*i].motion);
+
+        match source {
+            None | Some(Motion::Static) => Motion::Static,
+
+            Some(Motion::Path { waypoints, period }) => Motion::Path {
+                waypoints: waypoints
+                    .iter()
+                    .map(|waypoint| symmetry.map(from, to, *waypoint))
+                    .collect(),
+                period: *period,
+            },
+
+            Some(Motion::Orbit {
+                anchor,
+                radius,
+                period,
+                phase,
+            }) => {
+                let anchor = match *anchor {
+                    Anchor::Point(centre) => Anchor::Point(symmetry.map(from, to, centre)),
+                    Anchor::Planet(other) => {
+                        let partner = symmetry.map(from, to, self.planets[other].pos);
+
+                        self.symmetry_groups
+                            .iter()
+                            .find(|other_group| {
+                                other_group
+                                    .transform_of(LinkedBodies::Planets, other)
+                                    .is_some()
+                            })
+                            .and_then(|other_group| {
+                                other_group.members.iter().map(|(k, _)| *k).find(|k| {
+                                    (self.planets[*k].pos - partner).magnitude() < SAME_PLACE
+                                })
+                            })
+                            .map_or(Anchor::Planet(other), Anchor::Planet)
+                    }
+                };
+
+                Motion::Orbit {
+                    anchor,
+                    radius: *radius,
+                    period: *period,
+                    phase: *phase,
+                }
+            }
+        }
+    }
+
+    /// Delete a planet and any partners linked to it
+    pub fn remove_planet(&mut self, index: usize) {
+        for i in self.linked_indices(LinkedBodies::Planets, index) {
+            self.remove_single_planet(i);
+        }
+    }
+
+    /// Delete just this planet, leaving any partners linked to it where they are
+    pub fn remove_single_planet(&mut self, index: usize) {
+        self.unanchor(index);
+        self.planets.remove(index);
+        self.unlink(LinkedBodies::Planets, index);
+    }
+
+    /// Delete a wall and any partners linked to it
+    pub fn remove_wall(&mut self, index: usize) {
+        for i in self.linked_indices(LinkedBodies::Walls, index) {
+            self.remove_single_wall(i);
+        }
+    }
+
+    /// Delete just this wall, leaving any partners linked to it where they are
+    pub fn remove_single_wall(&mut self, index: usize) {
+        self.walls.remove(index);
+        self.unlink(LinkedBodies::Walls, index);
+    }
+
+    /// All the bodies linked to `index` (including itself), sorted from last to first so they can
+    /// be removed in order
+    fn linked_indices(&self, bodies: LinkedBodies, index: usize) -> Vec<usize> {
+        let mut indices = self
+            .symmetry_groups
+            .iter()
+            .find(|group| group.transform_of(bodies, index).is_some())
+            .map_or_else(
+                || vec![index],
+                |group| group.members.iter().map(|(i, _)| *i).collect(),
+            );
+
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices
+    }
+
+    /// Forget a removed body and shift the indices of the bodies after it
+    fn unlink(&mut self, bodies: LinkedBodies, removed: usize) {
+        for group in &mut self.symmetry_groups {
+            if group.bodies != bodies {
+                continue;
+            }
+
+            group.members.retain(|(i, _)| *i != removed);
+
+            for (i, _) in &mut group.members {
+                if *i > removed {
+                    *i -= 1;
+                }
+            }
+        }
+
+        self.symmetry_groups.retain(|group| group.members.len() > 1);
+    }
+}
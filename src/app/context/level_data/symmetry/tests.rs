@@ -0,0 +1,185 @@
+use super::{
+    Anchor, LevelData, LinkedBodies, Motion, Planet, SelectedBody, Symmetry, SymmetryMode, Vec2F,
+    Wall, WallMotion,
+};
+
+fn close(a: Vec2F, b: Vec2F) -> bool {
+    (a - b).magnitude() < 1e-9
+}
+
+fn level(mode: SymmetryMode) -> LevelData {
+    LevelData {
+        planets: Vec::new(),
+        symmetry: Symmetry::new(mode, Vec2F::new(200.0, 120.0)),
+        ..LevelData::default()
+    }
+}
+
+#[test]
+fn every_transform_is_undone_by_its_inverse() {
+    let pos = Vec2F::new(230.0, 100.0);
+
+    for mode in [
+        SymmetryMode::Horizontal,
+        SymmetryMode::Vertical,
+        SymmetryMode::Both,
+        SymmetryMode::Rotational(5),
+    ] {
+        let symmetry = Symmetry::new(mode, Vec2F::new(200.0, 120.0));
+
+        for transform in 0..symmetry.order() {
+            let copy = symmetry.apply(transform, pos);
+            assert!(
+                close(symmetry.invert(transform, copy), pos),
+                "{mode} {transform}"
+            );
+        }
+    }
+
+    let both = Symmetry::new(SymmetryMode::Both, Vec2F::new(200.0, 120.0));
+    assert!(close(both.apply(1, pos), Vec2F::new(170.0, 100.0)));
+    assert!(close(both.apply(2, pos), Vec2F::new(230.0, 140.0)));
+    assert!(close(both.apply(3, pos), Vec2F::new(170.0, 140.0)));
+}
+
+#[test]
+fn rotational_symmetry_is_capped() {
+    assert_eq!("rotational:12".parse(), Ok(SymmetryMode::Rotational(12)));
+    assert!("rotational:13".parse::<SymmetryMode>().is_err());
+    assert!("rotational:1".parse::<SymmetryMode>().is_err());
+    assert!("rotational:4000000".parse::<SymmetryMode>().is_err());
+}
+
+#[test]
+fn copies_on_a_mirror_line_are_not_stacked() {
+    let mut level_data = level(SymmetryMode::Both);
+
+    // On the vertical line, so the left-right mirror lands on the original
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(200.0, 60.0)));
+    assert_eq!(level_data.planets.len(), 2);
+    assert_eq!(level_data.symmetry_groups[0].members, vec![(0, 0), (1, 2)]);
+
+    // In the middle, every copy lands on the original, so nothing is linked
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(200.0, 120.0)));
+    assert_eq!(level_data.planets.len(), 3);
+    assert_eq!(level_data.symmetry_groups.len(), 1);
+
+    // A wall across the vertical line is its own reflection, drawn the other way round
+    level_data.spawn_wall(&Wall::new(Vec2F::new(180.0, 20.0), Vec2F::new(220.0, 20.0)));
+    assert_eq!(level_data.walls.len(), 2);
+}
+
+#[test]
+fn moving_one_copy_moves_the_rest_with_its_motion() {
+    let mut level_data = level(SymmetryMode::Horizontal);
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(150.0, 100.0)));
+    level_data.spawn_wall(&Wall::new(Vec2F::new(100.0, 50.0), Vec2F::new(120.0, 80.0)));
+
+    level_data.move_selection(SelectedBody::Planet(1), Vec2F::new(10.0, 5.0));
+    assert!(close(level_data.planets[0].pos, Vec2F::new(140.0, 105.0)));
+
+    level_data.planets[0].motion = Motion::Path {
+        waypoints: vec![Vec2F::new(140.0, 105.0), Vec2F::new(100.0, 105.0)],
+        period: 200.0,
+    };
+    level_data.sync_symmetry(SelectedBody::Planet(0));
+    assert_eq!(
+        level_data.planets[1].motion,
+        Motion::Path {
+            waypoints: vec![Vec2F::new(260.0, 105.0), Vec2F::new(300.0, 105.0)],
+            period: 200.0,
+        }
+    );
+
+    level_data.planets[1].motion = Motion::Orbit {
+        anchor: Anchor::Point(Vec2F::new(300.0, 105.0)),
+        radius: 40.0,
+        period: 150.0,
+        phase: std::f64::consts::PI,
+    };
+    level_data.sync_symmetry(SelectedBody::Planet(1));
+    let Motion::Orbit {
+        anchor: Anchor::Point(centre),
+        period,
+        ..
+    } = level_data.planets[0].motion
+    else {
+        panic!("The copy isn't orbiting");
+    };
+    assert!(close(centre, Vec2F::new(100.0, 105.0)));
+    assert!((period - 150.0).abs() < f64::EPSILON);
+
+    level_data.walls[0].motion = WallMotion::Rotate {
+        pivot: Vec2F::new(110.0, 65.0),
+        speed: 0.02,
+        sweep: 1.0,
+        ping_pong: false,
+    };
+    level_data.sync_symmetry(SelectedBody::WallHandle(0));
+    assert_eq!(
+        level_data.walls[1].motion,
+        WallMotion::Rotate {
+            pivot: Vec2F::new(290.0, 65.0),
+            speed: -0.02,
+            sweep: 1.0,
+            ping_pong: false,
+        }
+    );
+}
+
+#[test]
+fn removing_a_copy_removes_its_partners() {
+    let mut level_data = level(SymmetryMode::Rotational(3));
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(250.0, 120.0)));
+    level_data.symmetry.mode = SymmetryMode::Horizontal;
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(100.0, 40.0)));
+
+    level_data.remove_planet(1);
+    assert_eq!(level_data.planets.len(), 2);
+    assert_eq!(level_data.symmetry_groups.len(), 1);
+    assert_eq!(level_data.symmetry_groups[0].bodies, LinkedBodies::Planets);
+    assert_eq!(level_data.symmetry_groups[0].members, vec![(0, 0), (1, 1)]);
+
+    // Removing one of a pair on its own leaves nothing to link
+    level_data.remove_single_planet(0);
+    assert!(level_data.symmetry_groups.is_empty());
+}
+
+#[test]
+fn links_survive_saving_and_loading() {
+    let mut level_data = level(SymmetryMode::Rotational(4));
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(250.0, 120.0)));
+    level_data.symmetry = Symmetry::new(SymmetryMode::Vertical, Vec2F::new(180.0, 100.0));
+    level_data.spawn_wall(&Wall::new(Vec2F::new(100.0, 50.0), Vec2F::new(120.0, 80.0)));
+
+    let loaded = LevelData::parse(&level_data.to_string(), "test level")
+        .expect("The saved level should load");
+    assert_eq!(loaded.symmetry, level_data.symmetry);
+    assert_eq!(loaded.symmetry_groups, level_data.symmetry_groups);
+    assert_eq!(loaded.planets.len(), 4);
+}
+
+#[test]
+fn duplicates_are_linked_like_the_original() {
+    let mut level_data = level(SymmetryMode::Horizontal);
+    level_data.spawn_planet(&Planet::new(400.0, Vec2F::new(150.0, 60.0)));
+
+    let copy = level_data.duplicate_planet(1);
+
+    assert_eq!(copy, 3);
+    assert_eq!(level_data.planets.len(), 4);
+    assert_eq!(level_data.symmetry_groups[1].members, vec![(2, 0), (3, 1)]);
+
+    // Moving the duplicate moves its own partner, not the original's
+    level_data.planets[2].pos = Vec2F::new(120.0, 60.0);
+    level_data.sync_symmetry(SelectedBody::Planet(2));
+    assert!(close(level_data.planets[3].pos, Vec2F::new(280.0, 60.0)));
+    assert!(close(level_data.planets[1].pos, Vec2F::new(250.0, 60.0)));
+
+    // Without symmetry, a duplicate is a lone planet
+    level_data
+        .planets
+        .push(Planet::new(400.0, Vec2F::new(50.0, 50.0)));
+    assert_eq!(level_data.duplicate_planet(4), 5);
+    assert_eq!(level_data.symmetry_groups.len(), 2);
+}
@@ -0,0 +1,303 @@
+//! Checking a level for mistakes that are easy to make in the editor, or in a file edited by hand
+
+use std::fmt::Display;
+
+use super::{analysis, LevelData, Limits, Vec2F, MAX_PLANET_MASS};
+
+#[cfg(test)]
+mod tests;
+
+/// How close to a wall the probe can start before it counts as overlapping it
+const PLAYER_CLEARANCE: f64 = 4.0;
+/// How close two points can be before they count as the same place
+const SAME_PLACE: f64 = 0.5;
+/// Coordinates further from the level than this are surely a mistake
+const ABSURD_COORDINATE: f64 = 10_000.0;
+/// The size of the game's frame, which markers for bodies far outside it are kept to the edge of
+const FRAME_WIDTH: f64 = 400.0;
+const FRAME_HEIGHT: f64 = 240.0;
+
+/// How bad a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Probably a mistake, but the level still plays
+    Warning,
+    /// The level can't be played as intended
+    Error,
+}
+
+/// A problem found with a level
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The name of the rule that found it
+    pub rule: &'static str,
+    pub message: String,
+    /// Where the problem is, if it's somewhere in particular
+    pub pos: Option<Vec2F>,
+}
+
+impl Diagnostic {
+    const fn new(
+        severity: Severity,
+        rule: &'static str,
+        message: String,
+        pos: Option<Vec2F>,
+    ) -> Self {
+        Self {
+            severity,
+            rule,
+            message,
+            pos,
+        }
+    }
+}
+
+/// Diagnostics are written like `error[rule]: message`
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "{severity}[{}]: {}", self.rule, self.message)
+    }
+}
+
+/// A check of the level, adding anything wrong it finds to the list
+type Rule = fn(&LevelData, &mut Vec<Diagnostic>);
+
+/// The rules quick enough to run every time the level changes
+const QUICK_RULES: [Rule; 7] = [
+    invalid_coordinates,
+    player_in_planet,
+    player_on_wall,
+    target_outside_frame,
+    zero_length_walls,
+    duplicate_planets,
+    heavy_planets,
+];
+
+/// Check the level against every rule, worst problems first. `thorough` also runs the rules that
+/// have to fly the level, which can take a few seconds
+pub fn check(level_data: &LevelData, thorough: bool) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for rule in QUICK_RULES {
+        rule(level_data, &mut diagnostics);
+    }
+
+    // Flying a level with broken coordinates tells us nothing
+    if thorough && diagnostics.iter().all(|d| d.rule != "invalid-coordinates") {
+        target_unreachable(level_data, &mut diagnostics);
+    }
+
+    diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity));
+    diagnostics
+}
+
+fn invalid_coordinates(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    let mut points: Vec<(String, Vec2F)> = vec![
+        (String::from("The player"), level_data.player.pos),
+        (String::from("The target"), level_data.target.pos),
+    ];
+    points.extend(
+        level_data
+            .planets
+            .iter()
+            .enumerate()
+            .map(|(i, planet)| (format!("Planet {i}"), planet.pos)),
+    );
+    for (i, wall) in level_data.walls.iter().enumerate() {
+        points.push((format!("Wall {i}"), wall.pos1));
+        points.push((format!("Wall {i}"), wall.pos2));
+    }
+    for (i, wormhole) in level_data.wormholes.iter().enumerate() {
+        points.extend(wormhole.ends.map(|end| (format!("Wormhole {i}"), end)));
+    }
+    points.extend(
+        level_data
+            .checkpoints
+            .iter()
+            .enumerate()
+            .map(|(i, checkpoint)| (format!("Checkpoint {i}"), checkpoint.pos)),
+    );
+    points.extend(
+        level_data
+            .stars
+            .iter()
+            .enumerate()
+            .map(|(i, star)| (format!("Star {i}"), star.pos)),
+    );
+    for (i, zone) in level_data.zones.iter().enumerate() {
+        points.extend(
+            zone.corners
+                .iter()
+                .map(|corner| (format!("Zone {i}"), *corner)),
+        );
+    }
+
+    for (body, pos) in points {
+        if !pos.x.is_finite() || !pos.y.is_finite() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "invalid-coordinates",
+                format!("{body} has a position that isn't a number ({pos})"),
+                None,
+            ));
+        } else if pos.x.abs() > ABSURD_COORDINATE || pos.y.abs() > ABSURD_COORDINATE {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "invalid-coordinates",
+                format!("{body} is absurdly far away ({pos})"),
+                Some(Vec2F::new(
+                    pos.x.clamp(0.0, FRAME_WIDTH),
+                    pos.y.clamp(0.0, FRAME_HEIGHT),
+                )),
+            ));
+        }
+    }
+
+    for (i, planet) in level_data.planets.iter().enumerate() {
+        if !planet.mass.is_finite() {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "invalid-coordinates",
+                format!("Planet {i} has a mass that isn't a number"),
+                Some(planet.pos),
+            ));
+        }
+    }
+}
+
+fn player_in_planet(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    let pos = level_data.player.pos;
+
+    for (i, planet) in level_data.planets.iter().enumerate() {
+        let distance = planet.pos - pos;
+        if planet
+            .crash_radius_squared(&level_data.physics)
+            .is_some_and(|radius_squared| distance.dot(distance) < radius_squared)
+        {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "player-in-planet",
+                format!("The player starts inside planet {i}, so it crashes straight away"),
+                Some(pos),
+            ));
+        }
+    }
+}
+
+fn player_on_wall(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    let pos = level_data.player.pos;
+
+    for (i, wall) in level_data.walls.iter().enumerate() {
+        if distance_to_segment(pos, wall.pos1, wall.pos2) < PLAYER_CLEARANCE {
+            diagnostics.push(Diagnostic::new(
+                Severity::Error,
+                "player-on-wall",
+                format!("The player starts on top of wall {i}"),
+                Some(pos),
+            ));
+        }
+    }
+}
+
+fn target_outside_frame(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    let pos = level_data.target.pos;
+
+    if Limits::new(0.0, 0).is_out_of_bounds(pos) {
+        diagnostics.push(Diagnostic::new(
+            Severity::Error,
+            "target-outside-frame",
+            format!("The target is outside the 400x240 frame, at {pos}"),
+            Some(pos),
+        ));
+    }
+}
+
+fn zero_length_walls(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    for (i, wall) in level_data.walls.iter().enumerate() {
+        if (wall.pos2 - wall.pos1).magnitude() < SAME_PLACE {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "zero-length-wall",
+                format!("Wall {i} has no length, so nothing can hit it"),
+                Some(wall.pos1),
+            ));
+        }
+    }
+}
+
+fn duplicate_planets(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    let planets = &level_data.planets;
+
+    for (i, planet) in planets.iter().enumerate() {
+        if let Some(j) = planets[..i]
+            .iter()
+            .position(|other| (other.pos - planet.pos).magnitude() < SAME_PLACE)
+        {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "duplicate-planet",
+                format!("Planet {i} is stacked on top of planet {j}"),
+                Some(planet.pos),
+            ));
+        }
+    }
+}
+
+fn heavy_planets(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    for (i, planet) in level_data.planets.iter().enumerate() {
+        if planet.mass > MAX_PLANET_MASS {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                "planet-too-heavy",
+                format!(
+                    "Planet {i} has a mass of {}, more than the editor allows ({MAX_PLANET_MASS})",
+                    planet.mass
+                ),
+                Some(planet.pos),
+            ));
+        }
+    }
+}
+
+/// Levels with fuel might need the engine to reach the target, which the search doesn't try, so
+/// those only get a warning
+fn target_unreachable(level_data: &LevelData, diagnostics: &mut Vec<Diagnostic>) {
+    if analysis::reach_target(level_data).is_some() {
+        return;
+    }
+
+    let (severity, message) = if level_data.fuel > 0.0 {
+        (
+            Severity::Warning,
+            "No launch reaches the target without thrust",
+        )
+    } else {
+        (Severity::Error, "No launch reaches the target")
+    };
+
+    diagnostics.push(Diagnostic::new(
+        severity,
+        "target-unreachable",
+        String::from(message),
+        Some(level_data.target.pos),
+    ));
+}
+
+/// How far `pos` is from the nearest point on the line from `a` to `b`
+fn distance_to_segment(pos: Vec2F, a: Vec2F, b: Vec2F) -> f64 {
+    let along = b - a;
+    let length_squared = along.dot(along);
+    let t = if length_squared > 0.0 {
+        ((pos - a).dot(along) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+
+    (a + along * t - pos).magnitude()
+}
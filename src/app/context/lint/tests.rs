@@ -0,0 +1,96 @@
+use super::{check, LevelData, Severity, Vec2F};
+use crate::app::context::{Planet, Star, Wall};
+
+/// The rules that found something wrong with the level, worst first
+fn rules(level_data: &LevelData, thorough: bool) -> Vec<&'static str> {
+    check(level_data, thorough)
+        .iter()
+        .map(|diagnostic| diagnostic.rule)
+        .collect()
+}
+
+#[test]
+fn the_default_level_is_fine() {
+    assert_eq!(rules(&LevelData::default(), true), Vec::<&str>::new());
+}
+
+#[test]
+fn mistakes_made_in_the_editor_are_found() {
+    let mut level_data = LevelData::default();
+    let planet = level_data.planets[0].clone();
+    level_data.planets.push(planet);
+    level_data.walls = vec![
+        Wall::new(Vec2F::new(100.0, 50.0), Vec2F::new(100.0, 50.0)),
+        Wall::new(Vec2F::new(52.0, 100.0), Vec2F::new(52.0, 140.0)),
+    ];
+
+    assert_eq!(
+        rules(&level_data, false),
+        vec!["player-on-wall", "zero-length-wall", "duplicate-planet"]
+    );
+}
+
+#[test]
+fn broken_files_are_errors() {
+    let mut level_data = LevelData::default();
+    level_data
+        .planets
+        .push(Planet::new(20000.0, Vec2F::new(f64::NAN, 10.0)));
+    level_data
+        .planets
+        .push(Planet::new(400.0, level_data.player.pos));
+    level_data.target.pos = Vec2F::new(500.0, 120.0);
+
+    let diagnostics = check(&level_data, true);
+    let found: Vec<(Severity, &str)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.severity, diagnostic.rule))
+        .collect();
+
+    // The level isn't flown to look for a way to the target, with a position that isn't a number
+    assert_eq!(
+        found,
+        vec![
+            (Severity::Error, "invalid-coordinates"),
+            (Severity::Error, "player-in-planet"),
+            (Severity::Error, "target-outside-frame"),
+            (Severity::Warning, "planet-too-heavy"),
+        ]
+    );
+}
+
+#[test]
+fn bodies_absurdly_far_away_are_marked_at_the_edge_of_the_frame() {
+    let mut level_data = LevelData::default();
+    level_data
+        .stars
+        .push(Star::new(Vec2F::new(50_000.0, -20_000.0)));
+
+    let diagnostics = check(&level_data, false);
+    assert_eq!(diagnostics[0].rule, "invalid-coordinates");
+    assert_eq!(diagnostics[0].pos, Some(Vec2F::new(400.0, 0.0)));
+}
+
+#[test]
+fn a_walled_off_target_is_unreachable() {
+    let mut level_data = LevelData::default();
+    let target = level_data.target.pos;
+    let corners = [(-30.0, -30.0), (30.0, -30.0), (30.0, 30.0), (-30.0, 30.0)]
+        .map(|(x, y)| target + Vec2F::new(x, y));
+    level_data.walls = (0..4)
+        .map(|i| Wall::new(corners[i], corners[(i + 1) % 4]))
+        .collect();
+    level_data.solution = Some(Vec2F::new(2.0, 0.0));
+    // Every launch is tried, so keep each one short
+    level_data.limits.max_ticks = 200;
+
+    assert_eq!(rules(&level_data, false), Vec::<&str>::new());
+    assert_eq!(rules(&level_data, true), vec!["target-unreachable"]);
+
+    level_data.fuel = 100.0;
+    assert_eq!(
+        check(&level_data, true)[0].severity,
+        Severity::Warning,
+        "thrust might still get there"
+    );
+}
@@ -0,0 +1,314 @@
+//! Recording every launch, so a flight can be watched again exactly as it happened
+
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+use super::{Burn, LevelData, Simulation, SimulationEvent, Vec2F};
+
+#[cfg(test)]
+mod tests;
+
+/// How many ticks apart the snapshots playback seeks from are
+pub(super) const SNAPSHOT_SPACING: u32 = 50;
+
+/// Something the player did during a flight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    /// Held the probe's engine in a direction, or let go of it with zero
+    Thrust(Vec2F),
+    /// Changed the simulation speed
+    Speed(u32),
+    /// Paused the simulation
+    Pause,
+}
+
+/// Everything needed to fly a launch again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    /// The [`level_hash`] of the level when the flight was recorded
+    pub level_hash: u64,
+    pub parity: bool,
+    pub launch: Vec2F,
+    pub burns: Vec<Burn>,
+    /// What the player did, and on which tick
+    pub inputs: Vec<(u32, Input)>,
+}
+
+impl Replay {
+    /// Start recording a launch of the level as it's currently aimed
+    pub fn new(level_data: &LevelData) -> Self {
+        Self {
+            level_hash: level_hash(level_data),
+            parity: level_data.physics.parity,
+            launch: level_data.player.velocity,
+            burns: level_data.burns.clone(),
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Where the replays of the level at `level_path` are kept
+    pub fn path(level_path: &str) -> String {
+        Path::new(level_path)
+            .with_extension("rpl")
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Add the replay to the end of the level's replay file
+    pub fn save(&self, level_path: &str) -> Result<(), String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::path(level_path))
+            .map_err(|e| e.to_string())?;
+
+        write!(file, "{self}").map_err(|e| e.to_string())
+    }
+
+    /// Read every replay recorded for the level at `level_path`, oldest first
+    pub fn load_all(level_path: &str) -> Result<Vec<Self>, String> {
+        let text = fs::read_to_string(Self::path(level_path))
+            .map_err(|_| String::from("No replays recorded for this level"))?;
+
+        Self::parse_all(&text)
+    }
+
+    /// Read every replay in the text of a replay file
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, String> {
+        let mut replays: Vec<Self> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let mut words = line.split_whitespace();
+            let Some(kind) = words.next() else {
+                continue;
+            };
+            let level_hash = if kind == "replay" {
+                words
+                    .next()
+                    .and_then(|word| u64::from_str_radix(word, 16).ok())
+                    .ok_or_else(|| format!("Invalid level hash on line {}", number + 1))?
+            } else {
+                0
+            };
+            let mut number_word = || -> Result<f64, String> {
+                words
+                    .next()
+                    .and_then(|word| word.parse().ok())
+                    .ok_or_else(|| format!("Invalid replay on line {}", number + 1))
+            };
+
+            if kind == "replay" {
+                replays.push(Self {
+                    level_hash,
+                    parity: number_word()? != 0.0,
+                    launch: Vec2F::new(number_word()?, number_word()?),
+                    burns: Vec::new(),
+                    inputs: Vec::new(),
+                });
+                continue;
+            }
+
+            let replay = replays
+                .last_mut()
+                .ok_or_else(|| String::from("Replay file doesn't start with a replay"))?;
+            let tick = number_word()? as u32;
+
+            match kind {
+                "b" => replay.burns.push(Burn::new(
+                    tick,
+                    number_word()? as u32,
+                    Vec2F::new(number_word()?, number_word()?),
+                )),
+                "t" => replay.inputs.push((
+                    tick,
+                    Input::Thrust(Vec2F::new(number_word()?, number_word()?)),
+                )),
+                "s" => replay
+                    .inputs
+                    .push((tick, Input::Speed(number_word()? as u32))),
+                "p" => replay.inputs.push((tick, Input::Pause)),
+                _ => return Err(format!("Unknown replay line {}", number + 1)),
+            }
+        }
+
+        Ok(replays)
+    }
+
+    /// The level launched the way the replay was, ready to fly
+    pub fn start(&self, level_data: &LevelData) -> Simulation {
+        let mut simulation = Simulation::empty();
+        simulation.push(level_data);
+        simulation.physics.parity = self.parity;
+        simulation.player.velocity = self.launch;
+        simulation.burns.clone_from(&self.burns);
+
+        simulation
+    }
+
+    /// The simulation speed the player was watching at on `tick`
+    pub fn speed_at(&self, tick: u32) -> u32 {
+        self.inputs
+            .iter()
+            .rev()
+            .find_map(|(at, input)| match input {
+                Input::Speed(speed) if *at <= tick => Some(*speed),
+                _ => None,
+            })
+            .unwrap_or(1)
+    }
+}
+
+/// Apply any thrust the player changed to on the tick the simulation is about to fly. `inputs`
+/// are sorted by tick, and `cursor` is how many of them the flight has already passed, so flying
+/// through a replay looks at each input once
+pub fn apply_inputs(inputs: &[(u32, Input)], cursor: &mut usize, simulation: &mut Simulation) {
+    while let Some((tick, input)) = inputs.get(*cursor) {
+        if *tick > simulation.ticks {
+            break;
+        }
+
+        match input {
+            Input::Thrust(direction) if *tick == simulation.ticks => {
+                simulation.set_thrust(*direction);
+            }
+            _ => (),
+        }
+        *cursor += 1;
+    }
+}
+
+/// Replays are written as one line for the launch, then one for each burn and input
+impl Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "replay {:016x} {} {}",
+            self.level_hash,
+            u8::from(self.parity),
+            self.launch
+        )?;
+
+        for burn in &self.burns {
+            writeln!(f, "b {} {} {}", burn.tick, burn.duration, burn.direction)?;
+        }
+
+        for (tick, input) in &self.inputs {
+            match input {
+                Input::Thrust(direction) => writeln!(f, "t {tick} {direction}")?,
+                Input::Speed(speed) => writeln!(f, "s {tick} {speed}")?,
+                Input::Pause => writeln!(f, "p {tick}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A fingerprint of the level's contents, to tell whether a replay was recorded on the level as
+/// it is now. This is FNV-1a, so it stays the same between versions of the editor
+pub fn level_hash(level_data: &LevelData) -> u64 {
+    level_data
+        .to_string()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Watching a replay, which can be scrubbed through, stepped and played backwards
+pub struct Playback {
+    pub replay: Replay,
+    /// The flight every [`SNAPSHOT_SPACING`] ticks, starting from launch
+    snapshots: Vec<Simulation>,
+    /// How the flight ended
+    pub end: SimulationEvent,
+    /// The tick being watched
+    pub tick: u32,
+    /// 1 when playing forwards, -1 when playing backwards and 0 when paused
+    pub direction: i32,
+}
+
+impl Playback {
+    /// Fly the whole replay once, keeping snapshots to seek from
+    pub fn new(replay: Replay, level_data: &LevelData) -> Self {
+        let mut simulation = replay.start(level_data);
+
+        let mut snapshots = Vec::new();
+        let mut cursor = 0;
+        let end = loop {
+            if simulation.ticks == snapshots.len() as u32 * SNAPSHOT_SPACING {
+                snapshots.push(simulation.clone());
+            }
+
+            apply_inputs(&replay.inputs, &mut cursor, &mut simulation);
+            if let Some(event) = simulation.tick() {
+                break event;
+            }
+        };
+
+        Self {
+            replay,
+            snapshots,
+            end,
+            tick: 0,
+            direction: 1,
+        }
+    }
+
+    /// The flight as it was on the watched tick
+    pub fn simulation(&self) -> Simulation {
+        let snapshot = (self.tick / SNAPSHOT_SPACING) as usize;
+        let mut simulation = self.snapshots[snapshot.min(self.snapshots.len() - 1)].clone();
+        let mut cursor = self
+            .replay
+            .inputs
+            .partition_point(|(tick, _)| *tick < simulation.ticks);
+
+        while simulation.ticks < self.tick {
+            apply_inputs(&self.replay.inputs, &mut cursor, &mut simulation);
+            if simulation.tick().is_some() {
+                break;
+            }
+        }
+
+        simulation
+    }
+
+    /// How the flight ended, once playback has reached the end
+    pub fn event(&self) -> Option<SimulationEvent> {
+        (self.tick >= self.end.tick).then_some(self.end)
+    }
+
+    /// Move on by a frame at the speed the player was watching at. Playing forwards stops
+    /// wherever the player paused
+    pub fn advance(&mut self) {
+        let speed = self.replay.speed_at(self.tick);
+
+        match self.direction {
+            0 => (),
+            1 => {
+                let next = (self.tick + speed).min(self.end.tick);
+                let pause = self.replay.inputs.iter().find(|(tick, input)| {
+                    *input == Input::Pause && *tick > self.tick && *tick <= next
+                });
+
+                if let Some((tick, _)) = pause {
+                    self.tick = *tick;
+                    self.direction = 0;
+                } else {
+                    self.tick = next;
+                }
+            }
+            _ => self.tick = self.tick.saturating_sub(speed),
+        }
+    }
+
+    /// Move `change` ticks through the flight and pause there
+    pub fn step(&mut self, change: i32) {
+        self.tick = self.tick.saturating_add_signed(change).min(self.end.tick);
+        self.direction = 0;
+    }
+}
@@ -0,0 +1,74 @@
+use super::{apply_inputs, Input, LevelData, Playback, Replay, Vec2F};
+
+/// A launch into an empty level, which thrusts for a while then changes speed and pauses
+fn replay() -> (LevelData, Replay) {
+    let mut level_data = LevelData::default();
+    level_data.planets.clear();
+    level_data.fuel = 20.0;
+    level_data.player.velocity = Vec2F::new(1.0, 0.5);
+
+    let mut replay = Replay::new(&level_data);
+    replay.inputs = vec![
+        (10, Input::Thrust(Vec2F::new(0.0, -1.0))),
+        (15, Input::Speed(3)),
+        (25, Input::Thrust(Vec2F::ZERO)),
+        (40, Input::Pause),
+    ];
+
+    (level_data, replay)
+}
+
+#[test]
+fn replays_read_back_the_same() {
+    let (_, replay) = replay();
+
+    let text = format!("{replay}{replay}");
+    let replays = Replay::parse_all(&text).expect("replay file parses");
+
+    assert_eq!(replays, vec![replay.clone(), replay]);
+}
+
+#[test]
+fn playback_can_go_backwards() {
+    let (level_data, replay) = replay();
+    let mut playback = Playback::new(replay, &level_data);
+
+    playback.step(120);
+    let later = playback.simulation();
+    playback.step(-90);
+    let earlier = playback.simulation();
+    playback.step(90);
+
+    assert_eq!(earlier.ticks, 30);
+    assert_eq!(playback.simulation().player.pos, later.player.pos);
+    assert!(earlier.fuel < 20.0);
+}
+
+#[test]
+fn playback_stops_where_the_player_paused() {
+    let (level_data, replay) = replay();
+    let mut playback = Playback::new(replay, &level_data);
+
+    while playback.direction != 0 {
+        playback.advance();
+    }
+
+    assert_eq!(playback.tick, 40);
+}
+
+#[test]
+fn each_input_is_applied_on_its_own_tick_once() {
+    let (level_data, replay) = replay();
+    let mut simulation = replay.start(&level_data);
+    let mut cursor = 0;
+
+    while simulation.ticks < 30 {
+        apply_inputs(&replay.inputs, &mut cursor, &mut simulation);
+        simulation.tick();
+    }
+
+    // Past the second thrust, but not the pause
+    assert_eq!(cursor, 3);
+    let ticks: Vec<u32> = simulation.inputs.iter().map(|input| input.tick).collect();
+    assert_eq!(ticks, vec![10, 25]);
+}
@@ -1,4 +1,4 @@
-use super::{LevelData, Vec2F};
+use super::{Anchor, LevelData, Motion, Vec2F};
 
 #[derive(Debug, Clone, Copy)]
 pub enum WallEnd {
@@ -12,6 +12,19 @@ pub enum SelectedBody {
     Target,
     Planet(usize),
     Wall(usize, WallEnd),
+    /// The fixed point a planet orbits around
+    OrbitCentre(usize),
+    /// A waypoint along a planet's path. Waypoint 0 is the planet itself
+    Waypoint(usize, usize),
+    /// Where a sliding wall slides to, or the pivot of a rotating one
+    WallHandle(usize),
+    /// One end of a wormhole
+    Wormhole(usize, usize),
+    Checkpoint(usize),
+    Star(usize),
+    /// The handle in the middle of a zone, which moves the whole zone
+    Zone(usize),
+    ZoneCorner(usize, usize),
     None,
 }
 
@@ -51,6 +64,10 @@ impl Selection {
             return;
         }
 
+        if self.try_select_markers(level_data, mouse_pos) {
+            return;
+        }
+
         // Try walls
         for (i, wall) in level_data.walls.iter().enumerate() {
             if self.try_select_body(
@@ -72,19 +89,111 @@ impl Selection {
             }
         }
 
+        // Try wormholes
+        for (i, wormhole) in level_data.wormholes.iter().enumerate() {
+            for (end, pos) in wormhole.ends.iter().enumerate() {
+                if self.try_select_body(
+                    mouse_pos,
+                    SelectedBody::Wormhole(i, end),
+                    *pos,
+                    wormhole.radius,
+                ) {
+                    return;
+                }
+            }
+        }
+
+        // Try motion handles
+        for (i, wall) in level_data.walls.iter().enumerate() {
+            if let Some(handle) = wall.motion_handle() {
+                if self.try_select_body(mouse_pos, SelectedBody::WallHandle(i), handle, 6.0) {
+                    return;
+                }
+            }
+        }
+
+        for (i, planet) in level_data.planets.iter().enumerate() {
+            match &planet.motion {
+                Motion::Orbit {
+                    anchor: Anchor::Point(centre),
+                    ..
+                } if self.try_select_body(
+                    mouse_pos,
+                    SelectedBody::OrbitCentre(i),
+                    *centre,
+                    6.0,
+                ) =>
+                {
+                    return;
+                }
+
+                Motion::Path { waypoints, .. } => {
+                    for (k, waypoint) in waypoints.iter().enumerate().skip(1) {
+                        if self.try_select_body(
+                            mouse_pos,
+                            SelectedBody::Waypoint(i, k),
+                            *waypoint,
+                            6.0,
+                        ) {
+                            return;
+                        }
+                    }
+                }
+
+                _ => (),
+            }
+        }
+
         // Try planets
         for (i, planet) in level_data.planets.iter().enumerate() {
             if self.try_select_body(
                 mouse_pos,
                 SelectedBody::Planet(i),
                 planet.pos,
-                planet.mass.abs() / 12.0,
+                planet.radius(),
             ) {
                 return;
             }
         }
     }
 
+    /// Try the bodies that only mark places in the level: stars, checkpoints, wormholes and zones
+    fn try_select_markers(&mut self, level_data: &LevelData, mouse_pos: Vec2F) -> bool {
+        // Try stars
+        for (i, star) in level_data.stars.iter().enumerate() {
+            if self.try_select_body(mouse_pos, SelectedBody::Star(i), star.pos, 8.0) {
+                return true;
+            }
+        }
+
+        // Try checkpoints
+        for (i, checkpoint) in level_data.checkpoints.iter().enumerate() {
+            if self.try_select_body(
+                mouse_pos,
+                SelectedBody::Checkpoint(i),
+                checkpoint.pos,
+                checkpoint.radius + 2.0,
+            ) {
+                return true;
+            }
+        }
+
+        // Try zones
+        for (i, zone) in level_data.zones.iter().enumerate() {
+            for (k, corner) in zone.corners.iter().enumerate() {
+                if self.try_select_body(mouse_pos, SelectedBody::ZoneCorner(i, k), *corner, 6.0) {
+                    return true;
+                }
+            }
+
+            if self.try_select_body(mouse_pos, SelectedBody::Zone(i), zone.centre(), 6.0) {
+                return true;
+            }
+        }
+
+        false
+    }
+
     /// Attempt to select a body. Returns true if the body is actually selected
     fn try_select_body(
         &mut self,
@@ -1,24 +1,92 @@
 use super::LevelData;
 
+mod checkpoint;
+mod integrator;
+mod limits;
+mod motion;
+pub mod parity;
+mod physics;
 mod planet;
 mod player;
+mod star;
 mod target;
+mod thrust;
 mod vec2f;
 mod wall;
+mod wormhole;
+mod zone;
 
-pub use planet::Planet;
+pub use checkpoint::Checkpoint;
+pub use integrator::Integrator;
+pub use limits::Limits;
+use limits::{OrbitDetector, ProbeState};
+pub use motion::{position_at, Anchor, Motion, WallMotion};
+pub use physics::PhysicsProfile;
+pub use planet::{Planet, PlanetKind, MAX_PLANET_MASS};
 pub use player::Player;
+pub use star::Star;
 pub use target::Target;
+pub use thrust::{Burn, ThrustInput, THRUST};
 pub use vec2f::Vec2F;
-pub use wall::Wall;
+pub use wall::{Wall, WallKind};
+pub use wormhole::Wormhole;
+pub use zone::{Zone, ZoneEffect};
 
-// const G: f64 = 6.67430e-11;
-const G: f64 = 0.55;
+/// How many times the probe can bounce off walls in one step, so it can't get stuck forever
+/// between two of them
+const MAX_BOUNCES: u32 = 8;
+/// How far off a bouncy wall the probe is put after bouncing
+const BOUNCE_GAP: f64 = 1e-6;
 
+#[cfg(test)]
+mod tests;
+
+/// What brought a flight to an end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    /// The probe reached the target, after passing through every checkpoint
+    Target,
+    /// The index of the planet the probe crashed into
+    Planet(usize),
+    /// The index of the wall the probe crashed into
+    Wall(usize),
+    /// The probe flew further past the edge of the screen than the level's margin allows
+    OutOfBounds,
+    /// The flight lasted longer than the level's maximum duration
+    Timeout,
+    /// The probe returned to a position and velocity it already had, so it would loop forever.
+    /// Holds the period of the orbit in ticks
+    Orbit(u32),
+}
+
+/// A short description, e.g. "planet 2"
+impl std::fmt::Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Target => write!(f, "target"),
+            Self::Planet(i) => write!(f, "planet {i}"),
+            Self::Wall(i) => write!(f, "wall {i}"),
+            Self::OutOfBounds => write!(f, "out of bounds"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::Orbit(period) => write!(f, "orbit of {period} ticks"),
+        }
+    }
+}
+
+/// The outcome of a flight
 #[derive(Debug, Clone, Copy)]
-pub enum Event {
-    Crashed,
-    Won,
+pub struct Event {
+    pub cause: Cause,
+    /// The number of ticks the probe flew for
+    pub tick: u32,
+    /// Where the probe was when the flight ended
+    pub pos: Vec2F,
+    /// The velocity of the probe when the flight ended
+    pub velocity: Vec2F,
+    /// How many stars were collected
+    pub stars: usize,
+    /// Points for every star collected, plus a bonus for reaching the target quickly
+    pub score: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -27,6 +95,34 @@ pub struct Simulation {
     pub target: Target,
     pub planets: Vec<Planet>,
     pub walls: Vec<Wall>,
+    pub wormholes: Vec<Wormhole>,
+    pub checkpoints: Vec<Checkpoint>,
+    /// Whether the checkpoints have to be passed in order
+    pub checkpoints_ordered: bool,
+    /// The tick each checkpoint was passed on, if it has been
+    pub checkpoints_passed: Vec<Option<u32>>,
+    pub stars: Vec<Star>,
+    /// The tick each star was collected on, if it has been
+    pub stars_collected: Vec<Option<u32>>,
+    pub zones: Vec<Zone>,
+    /// How many more ticks the probe's engine can burn for
+    pub fuel: f64,
+    /// Burns planned before launch, which fire unless the player is holding a thrust of their own
+    pub burns: Vec<Burn>,
+    /// The direction the player is holding the probe's engine in, or zero
+    thrust: Vec2F,
+    /// Every change to the thrust the player held during the flight
+    pub inputs: Vec<ThrustInput>,
+    pub physics: PhysicsProfile,
+    pub limits: Limits,
+    /// How many steps each tick is split into. 1 matches the game
+    pub substeps: u32,
+    /// The number of ticks simulated since launch
+    pub ticks: u32,
+    orbit_detector: OrbitDetector,
+    /// The wormhole and end the probe last came out of, which it can't go back into until it
+    /// has left that end
+    wormhole_exit: Option<(usize, usize)>,
     pub speed: u32,
     pub playing: bool,
 }
@@ -38,6 +134,28 @@ impl Simulation {
             target: Target::from_nums(&[20.0, 330.0, 120.0]),
             planets: Vec::new(),
             walls: Vec::new(),
+            wormholes: Vec::new(),
+            checkpoints: Vec::new(),
+            checkpoints_ordered: false,
+            checkpoints_passed: Vec::new(),
+            stars: Vec::new(),
+            stars_collected: Vec::new(),
+            zones: Vec::new(),
+            fuel: 0.0,
+            burns: Vec::new(),
+            thrust: Vec2F::ZERO,
+            inputs: Vec::new(),
+            physics: PhysicsProfile::GAME,
+            limits: Limits::DEFAULT,
+            substeps: 1,
+            ticks: 0,
+            orbit_detector: OrbitDetector::new(ProbeState {
+                pos: Vec2F::ZERO,
+                velocity: Vec2F::ZERO,
+                wormhole_exit: None,
+                checkpoints: 0,
+            }),
+            wormhole_exit: None,
             speed: 1,
             playing: true,
         }
@@ -49,6 +167,23 @@ impl Simulation {
         self.target = level_data.target.clone();
         self.planets.clone_from(&level_data.planets);
         self.walls.clone_from(&level_data.walls);
+        self.wormholes.clone_from(&level_data.wormholes);
+        self.checkpoints.clone_from(&level_data.checkpoints);
+        self.checkpoints_ordered = level_data.checkpoints_ordered;
+        self.checkpoints_passed = vec![None; self.checkpoints.len()];
+        self.stars.clone_from(&level_data.stars);
+        self.stars_collected = vec![None; self.stars.len()];
+        self.zones.clone_from(&level_data.zones);
+        self.fuel = level_data.fuel;
+        self.burns.clone_from(&level_data.burns);
+        self.thrust = Vec2F::ZERO;
+        self.inputs.clear();
+        self.physics = level_data.physics;
+        self.limits = level_data.limits;
+        self.substeps = level_data.substeps;
+        self.ticks = 0;
+        self.wormhole_exit = None;
+        self.orbit_detector = OrbitDetector::new(self.probe_state());
         self.playing = true;
     }
 
@@ -58,49 +193,435 @@ impl Simulation {
         }
 
         for _ in 0..self.speed {
-            if self.gravitate_player() || self.is_colliding_with_walls() {
-                return Some(Event::Crashed);
+            self.ticks += 1;
+
+            let substeps = if self.physics.parity {
+                1
+            } else {
+                self.substeps.max(1)
+            };
+
+            let dt = 1.0 / substeps as f64;
+            for step in 0..substeps {
+                let time = f64::from(step).mul_add(dt, f64::from(self.ticks - 1));
+                self.move_planets(time);
+                self.burn(dt);
+
+                let to = match self.integrate(dt) {
+                    Ok(to) => to,
+                    Err(i) => return Some(self.end(Cause::Planet(i))),
+                };
+
+                if let Some(cause) = self.move_player(to, time, dt) {
+                    return Some(self.end(cause));
+                }
+
+                self.enter_wormholes();
+                self.pass_checkpoints();
+                self.collect_stars();
+
+                // Checked every substep so a fast probe can't skip over the target
+                if self.is_target_open() && self.is_touching_target() {
+                    return Some(self.end(Cause::Target));
+                }
+
+                if self.limits.is_out_of_bounds(self.player.pos) {
+                    return Some(self.end(Cause::OutOfBounds));
+                }
+            }
+
+            // Only whole ticks are compared, since substeps of different ticks are not
+            // interchangeable. With moving bodies, the probe coming back to the same place
+            // doesn't mean the rest of the level has, and with fuel left the player could
+            // still thrust out of the orbit
+            let state = self.probe_state();
+            if let Some(period) = self
+                .orbit_detector
+                .step(state)
+                .filter(|_| !self.has_moving_bodies() && self.fuel <= 0.0)
+            {
+                return Some(self.end(Cause::Orbit(period)));
             }
 
-            if self.is_touching_target() {
-                return Some(Event::Won);
+            if self.ticks >= self.limits.max_ticks {
+                return Some(self.end(Cause::Timeout));
             }
         }
 
         None
     }
 
-    /// Returns `true` if the player crashes
-    fn gravitate_player(&mut self) -> bool {
-        for planet in &self.planets {
+    pub fn has_moving_planets(&self) -> bool {
+        self.planets
+            .iter()
+            .any(|planet| planet.motion != Motion::Static)
+    }
+
+    pub fn has_moving_bodies(&self) -> bool {
+        self.has_moving_planets()
+            || self
+                .walls
+                .iter()
+                .any(|wall| wall.motion != WallMotion::Static)
+    }
+
+    /// Where every wall is after `time` ticks
+    pub fn walls_at(&self, time: f64) -> Vec<Wall> {
+        self.walls.iter().map(|wall| wall.at(time)).collect()
+    }
+
+    /// Put every moving planet where it is after `time` ticks
+    pub fn move_planets(&mut self, time: f64) {
+        if !self.has_moving_planets() {
+            return;
+        }
+
+        let positions: Vec<Vec2F> = (0..self.planets.len())
+            .map(|i| position_at(&self.planets, i, time))
+            .collect();
+
+        for (planet, pos) in self.planets.iter_mut().zip(positions) {
+            planet.pos = pos;
+        }
+    }
+
+    fn end(&self, cause: Cause) -> Event {
+        let stars = self.stars_collected();
+
+        // Every tick left over when the target is reached is worth a tenth of a point
+        let time_bonus = match cause {
+            Cause::Target => self.limits.max_ticks.saturating_sub(self.ticks) / 10,
+            _ => 0,
+        };
+
+        Event {
+            cause,
+            tick: self.ticks,
+            pos: self.player.pos,
+            velocity: self.player.velocity,
+            stars,
+            score: stars as u32 * Star::POINTS + time_bonus,
+        }
+    }
+
+    /// Advance the player's velocity by `dt` ticks with the profile's integrator, returning where
+    /// the player should move to. Returns the index of the planet the player is already inside
+    /// as an error, if any
+    fn integrate(&mut self, dt: f64) -> Result<Vec2F, usize> {
+        let integrator = if self.physics.parity {
+            Integrator::SemiImplicitEuler
+        } else {
+            self.physics.integrator
+        };
+
+        if integrator == Integrator::SemiImplicitEuler {
+            // The game adds each planet's pull to the velocity one at a time, which rounds
+            // differently from `Integrator::advance`
+            if let Some(i) = self.gravitate_player(dt) {
+                return Err(i);
+            }
+
+            return Ok(self.player.pos + self.player.velocity * dt);
+        }
+
+        if let Some(i) = self.planets.iter().position(|planet| {
+            let distance = planet.pos - self.player.pos;
+            planet
+                .crash_radius_squared(&self.physics)
+                .is_some_and(|radius_squared| distance.dot(distance) < radius_squared)
+        }) {
+            return Err(i);
+        }
+
+        let (_, _, drag) = self.zone_effects(self.player.pos);
+        let (to, velocity) = integrator.advance(self.player.pos, self.player.velocity, dt, |pos| {
+            self.acceleration_at(pos)
+        });
+        self.player.velocity = velocity * drag_factor(drag, dt);
+
+        Ok(to)
+    }
+
+    /// The combined pull of every planet at `pos`, and the push of any zones there
+    pub fn acceleration_at(&self, pos: Vec2F) -> Vec2F {
+        let (gravity_scale, wind, _) = self.zone_effects(pos);
+
+        let gravity = self
+            .pulls_at(pos)
+            .fold(Vec2F::ZERO, |total, pull| total + pull);
+
+        gravity * gravity_scale + wind
+    }
+
+    /// Each planet's pull at `pos`, in the same order as the planets, before any zone scales it
+    pub fn pulls_at(&self, pos: Vec2F) -> impl Iterator<Item = Vec2F> + '_ {
+        self.planets.iter().map(move |planet| {
+            let distance = planet.pos - pos;
+            let magnitude = distance.dot(distance);
+
+            distance.normalised() * (self.physics.gravity * planet.pull() / magnitude)
+        })
+    }
+
+    /// The combined effect of every zone at `pos`: how much the planets' gravity is multiplied
+    /// by, the acceleration of any wind and the drag
+    pub fn zone_effects(&self, pos: Vec2F) -> (f64, Vec2F, f64) {
+        let mut effects = (1.0, Vec2F::ZERO, 0.0);
+
+        for zone in self.zones.iter().filter(|zone| zone.contains(pos)) {
+            match zone.effect {
+                ZoneEffect::Gravity(scale) => effects.0 *= scale,
+                ZoneEffect::Wind(acceleration) => effects.1 += acceleration,
+                ZoneEffect::Drag(drag) => effects.2 += drag,
+            }
+        }
+
+        effects
+    }
+
+    /// The total energy of the probe per unit mass: its kinetic energy plus its potential energy
+    /// in every planet's gravity. An ideal integrator would keep this constant over a flight
+    pub fn energy(&self) -> f64 {
+        let kinetic = 0.5 * self.player.velocity.dot(self.player.velocity);
+
+        kinetic + self.potential_at(self.player.pos)
+    }
+
+    /// The potential energy per unit mass at `pos` in every planet's gravity
+    pub fn potential_at(&self, pos: Vec2F) -> f64 {
+        self.planets
+            .iter()
+            .map(|planet| -self.physics.gravity * planet.pull() / (planet.pos - pos).magnitude())
+            .sum()
+    }
+
+    /// Accelerate the player towards each planet over `dt` ticks. Returns the index of the planet
+    /// the player is already inside, if any
+    fn gravitate_player(&mut self, dt: f64) -> Option<usize> {
+        let (gravity_scale, wind, drag) = self.zone_effects(self.player.pos);
+
+        for (i, planet) in self.planets.iter().enumerate() {
             let distance = planet.pos - self.player.pos;
             let angle = distance.angle();
 
             let magnitude = distance.x.mul_add(distance.x, distance.y.powi(2));
-            let acceleration = G * planet.mass / magnitude;
+            let acceleration = self.physics.gravity * planet.pull() / magnitude * gravity_scale;
 
-            self.player.velocity += Vec2F::new(angle.cos(), angle.sin()) * acceleration;
+            self.player.velocity += Vec2F::new(angle.cos(), angle.sin()) * (acceleration * dt);
 
-            if magnitude < planet.mass.powi(2) / 144.0 {
-                return true;
+            if planet
+                .crash_radius_squared(&self.physics)
+                .is_some_and(|radius_squared| magnitude < radius_squared)
+            {
+                return Some(i);
             }
         }
 
-        self.player.pos += self.player.velocity;
+        // Outside of zones these leave the velocity exactly as it was, so flights still match
+        // the game
+        self.player.velocity += wind * dt;
+        self.player.velocity = self.player.velocity * drag_factor(drag, dt);
 
-        false
+        None
     }
 
-    fn is_colliding_with_walls(&self) -> bool {
-        let player_line = Wall::new(self.player.pos, self.player.pos - self.player.velocity);
+    /// Move the probe to `to`, taking `dt` ticks from `time` ticks into the flight. The path is
+    /// swept against every planet and wall so fast probes can't skip through them. If anything
+    /// is hit, the probe is stopped at the exact point of impact, unless it was a bouncy wall,
+    /// which reflects the rest of the path from there. Parity mode treats every wall as deadly,
+    /// like the game
+    fn move_player(&mut self, mut to: Vec2F, mut time: f64, mut dt: f64) -> Option<Cause> {
+        let mut from = self.player.pos;
 
-        for wall in &self.walls {
-            if wall.intersects(&player_line) {
-                return true;
+        if self.physics.parity {
+            self.player.pos = to;
+            let previous_pos = to - self.player.velocity;
+
+            return self
+                .walls
+                .iter()
+                .position(|wall| wall.at(time + dt).crosses(to, previous_pos))
+                .map(Cause::Wall);
+        }
+
+        for _ in 0..MAX_BOUNCES {
+            let Some((t, cause)) = self.first_impact(from, to, time, dt) else {
+                self.player.pos = to;
+                return None;
+            };
+
+            let impact = from + (to - from) * t;
+
+            let Cause::Wall(i) = cause else {
+                self.player.pos = impact;
+                return Some(cause);
+            };
+            let wall = self.walls[i].at(t.mul_add(dt, time));
+            let WallKind::Bouncy { restitution } = wall.kind else {
+                self.player.pos = impact;
+                return Some(cause);
+            };
+
+            // Reflect the rest of the step too, and start it just off the wall on the side the
+            // probe came from so it doesn't hit the same spot again
+            let normal = wall.normal();
+            let back = normal * -(to - from).dot(normal).signum();
+
+            self.player.velocity = self.player.velocity.reflected(normal, restitution);
+            from = impact + back * BOUNCE_GAP;
+            to = from + (to - impact).reflected(normal, restitution);
+            time = t.mul_add(dt, time);
+            dt *= 1.0 - t;
+        }
+
+        self.player.pos = from;
+
+        None
+    }
+
+    /// The first planet or wall the probe hits flying from `from` to `to`, and the fraction of the
+    /// way along it does. One-way walls don't count when the probe is going the way they let it
+    fn first_impact(&self, from: Vec2F, to: Vec2F, time: f64, dt: f64) -> Option<(f64, Cause)> {
+        let planet_impacts = self.planets.iter().enumerate().filter_map(|(i, planet)| {
+            let time = planet.time_of_impact(from, to, &self.physics)?;
+            Some((time, Cause::Planet(i)))
+        });
+
+        let wall_impacts = self.walls.iter().enumerate().filter_map(|(i, wall)| {
+            let t = wall.intersects(from, to, time, dt)?;
+
+            let passes = wall.kind == WallKind::OneWay
+                && (to - from).dot(wall.at(t.mul_add(dt, time)).normal()) > 0.0;
+
+            (!passes).then_some((t, Cause::Wall(i)))
+        });
+
+        planet_impacts
+            .chain(wall_impacts)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    pub const fn thrust(&self) -> Vec2F {
+        self.thrust
+    }
+
+    /// Hold the probe's engine in `direction`, or let go of it with zero
+    pub fn set_thrust(&mut self, direction: Vec2F) {
+        if direction == self.thrust {
+            return;
+        }
+
+        self.thrust = direction;
+        self.inputs.push(ThrustInput {
+            tick: self.ticks,
+            direction,
+        });
+    }
+
+    /// The direction the engine is pushing the probe in this tick, if it's burning at all
+    pub fn burn_direction(&self) -> Option<Vec2F> {
+        if self.fuel <= 0.0 {
+            return None;
+        }
+
+        if self.thrust != Vec2F::ZERO {
+            return Some(self.thrust.normalised());
+        }
+
+        let elapsed = self.ticks.saturating_sub(1);
+        self.burns
+            .iter()
+            .find(|burn| burn.is_burning(elapsed))
+            .map(|burn| burn.direction)
+    }
+
+    /// Fire the engine for `dt` ticks, as long as there's fuel left
+    fn burn(&mut self, dt: f64) {
+        let Some(direction) = self.burn_direction() else {
+            return;
+        };
+
+        let burnt = dt.min(self.fuel);
+        self.player.velocity += direction * (THRUST * burnt);
+        self.fuel -= burnt;
+    }
+
+    /// Send the probe out of the other end of any wormhole it has flown into
+    fn enter_wormholes(&mut self) {
+        let pos = self.player.pos;
+
+        if let Some((i, end)) = self.wormhole_exit {
+            if self.wormholes[i].end_at(pos) == Some(end) {
+                return;
+            }
+
+            self.wormhole_exit = None;
+        }
+
+        for (i, wormhole) in self.wormholes.iter().enumerate() {
+            if let Some(end) = wormhole.end_at(pos) {
+                (self.player.pos, self.player.velocity) =
+                    wormhole.teleport(end, pos, self.player.velocity);
+                self.wormhole_exit = Some((i, 1 - end));
+
+                return;
             }
         }
+    }
+
+    /// Mark any checkpoints the probe is flying through as passed. Ordered checkpoints only count
+    /// once the one before has been passed
+    fn pass_checkpoints(&mut self) {
+        for (i, checkpoint) in self.checkpoints.iter().enumerate() {
+            if self.checkpoints_passed[i].is_some() {
+                continue;
+            }
 
-        false
+            if checkpoint.contains(self.player.pos) {
+                self.checkpoints_passed[i] = Some(self.ticks);
+            }
+
+            if self.checkpoints_ordered {
+                break;
+            }
+        }
+    }
+
+    fn collect_stars(&mut self) {
+        for (star, collected) in self.stars.iter().zip(&mut self.stars_collected) {
+            if collected.is_none() && star.contains(self.player.pos) {
+                *collected = Some(self.ticks);
+            }
+        }
+    }
+
+    pub fn stars_collected(&self) -> usize {
+        self.stars_collected
+            .iter()
+            .filter(|collected| collected.is_some())
+            .count()
+    }
+
+    /// How many checkpoints have been passed
+    pub fn checkpoint_progress(&self) -> usize {
+        self.checkpoints_passed
+            .iter()
+            .filter(|passed| passed.is_some())
+            .count()
+    }
+
+    fn probe_state(&self) -> ProbeState {
+        ProbeState {
+            pos: self.player.pos,
+            velocity: self.player.velocity,
+            wormhole_exit: self.wormhole_exit,
+            checkpoints: self.checkpoint_progress(),
+        }
+    }
+
+    /// Whether the target counts yet, once every checkpoint has been passed
+    pub fn is_target_open(&self) -> bool {
+        self.checkpoint_progress() == self.checkpoints.len()
     }
 
     fn is_touching_target(&self) -> bool {
@@ -113,3 +634,8 @@ impl Simulation {
         distance < self.target.size.powi(2)
     }
 }
+
+/// How much of the probe's velocity is left after `dt` ticks of `drag`
+fn drag_factor(drag: f64, dt: f64) -> f64 {
+    drag.mul_add(-dt, 1.0).max(0.0)
+}
@@ -0,0 +1,32 @@
+use super::Vec2F;
+
+/// A ring the probe has to fly through before the target counts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub pos: Vec2F,
+    pub radius: f64,
+}
+
+impl Checkpoint {
+    pub const fn new(pos: Vec2F, radius: f64) -> Self {
+        Self { pos, radius }
+    }
+
+    pub const fn from_nums(nums: &[f64]) -> Self {
+        assert!(
+            nums.len() == 3,
+            "Wrong number of items passed to `Checkpoint::from_nums`"
+        );
+
+        Self::new(Vec2F::new(nums[0], nums[1]), nums[2])
+    }
+
+    pub fn contains(&self, pos: Vec2F) -> bool {
+        (self.pos - pos).magnitude() < self.radius
+    }
+
+    pub fn change_size(&mut self, change: f64) {
+        self.radius *= 1.0 + change;
+        self.radius = self.radius.max(5.0);
+    }
+}
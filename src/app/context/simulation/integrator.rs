@@ -0,0 +1,93 @@
+use super::Vec2F;
+
+/// How the probe's position and velocity are advanced each tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Integrator {
+    /// Add the acceleration to the velocity, then the velocity to the position. This is what the
+    /// game does
+    SemiImplicitEuler,
+    /// Average the acceleration at the start and end of the step. Conserves energy far better
+    /// than Euler for the same cost
+    VelocityVerlet,
+    /// Classic fourth order Runge-Kutta. The most accurate, at four evaluations per step
+    RungeKutta4,
+}
+
+impl Integrator {
+    pub const ALL: [Self; 3] = [
+        Self::SemiImplicitEuler,
+        Self::VelocityVerlet,
+        Self::RungeKutta4,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::SemiImplicitEuler => "euler",
+            Self::VelocityVerlet => "verlet",
+            Self::RungeKutta4 => "rk4",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|integrator| integrator.name() == name)
+            .ok_or_else(|| format!("Unknown integrator `{name}`"))
+    }
+
+    pub const fn next(self) -> Self {
+        match self {
+            Self::SemiImplicitEuler => Self::VelocityVerlet,
+            Self::VelocityVerlet => Self::RungeKutta4,
+            Self::RungeKutta4 => Self::SemiImplicitEuler,
+        }
+    }
+
+    /// Advance `pos` and `velocity` by `dt` ticks, given the acceleration at any position.
+    /// Returns the new position and velocity
+    pub fn advance(
+        self,
+        pos: Vec2F,
+        velocity: Vec2F,
+        dt: f64,
+        acceleration: impl Fn(Vec2F) -> Vec2F,
+    ) -> (Vec2F, Vec2F) {
+        match self {
+            Self::SemiImplicitEuler => {
+                let velocity = velocity + acceleration(pos) * dt;
+
+                (pos + velocity * dt, velocity)
+            }
+
+            Self::VelocityVerlet => {
+                let start_acceleration = acceleration(pos);
+                let new_pos = pos + velocity * dt + start_acceleration * (0.5 * dt * dt);
+                let end_acceleration = acceleration(new_pos);
+
+                let new_velocity = velocity + (start_acceleration + end_acceleration) * (0.5 * dt);
+
+                (new_pos, new_velocity)
+            }
+
+            Self::RungeKutta4 => {
+                let k1_pos = velocity;
+                let k1_velocity = acceleration(pos);
+
+                let k2_pos = velocity + k1_velocity * (0.5 * dt);
+                let k2_velocity = acceleration(pos + k1_pos * (0.5 * dt));
+
+                let k3_pos = velocity + k2_velocity * (0.5 * dt);
+                let k3_velocity = acceleration(pos + k2_pos * (0.5 * dt));
+
+                let k4_pos = velocity + k3_velocity * dt;
+                let k4_velocity = acceleration(pos + k3_pos * dt);
+
+                let new_pos = pos + (k1_pos + (k2_pos + k3_pos) * 2.0 + k4_pos) * (dt / 6.0);
+                let new_velocity = velocity
+                    + (k1_velocity + (k2_velocity + k3_velocity) * 2.0 + k4_velocity) * (dt / 6.0);
+
+                (new_pos, new_velocity)
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use super::Vec2F;
+
+/// The size of the area the level is played in
+const FIELD_WIDTH: f64 = 400.0;
+const FIELD_HEIGHT: f64 = 240.0;
+
+/// The furthest outside the screen a level can let the probe wander
+const MAX_MARGIN: f64 = 2000.0;
+/// The longest flight a level can ask for, so a typo can't hang the editor
+const MAX_TICKS: u32 = 100_000;
+
+/// When a flight should be ended early because the probe is never coming back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// How far the probe may fly outside the edge of the screen before it's considered lost
+    pub margin: f64,
+    /// The longest a flight can last, in ticks
+    pub max_ticks: u32,
+}
+
+impl Limits {
+    pub const DEFAULT: Self = Self::new(100.0, 3000);
+
+    pub const fn new(margin: f64, max_ticks: u32) -> Self {
+        Self { margin, max_ticks }
+    }
+
+    pub fn is_out_of_bounds(&self, pos: Vec2F) -> bool {
+        pos.x < -self.margin
+            || pos.y < -self.margin
+            || pos.x > FIELD_WIDTH + self.margin
+            || pos.y > FIELD_HEIGHT + self.margin
+    }
+
+    /// Parse the arguments of a `limits` metadata line
+    pub fn from_words<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Self, String> {
+        let margin = words
+            .next()
+            .and_then(|w| w.parse::<f64>().ok())
+            .ok_or_else(|| String::from("Missing out of bounds margin"))?;
+
+        let max_ticks = words
+            .next()
+            .and_then(|w| w.parse::<u32>().ok())
+            .ok_or_else(|| String::from("Missing maximum flight duration"))?;
+
+        if !(0.0..=MAX_MARGIN).contains(&margin) {
+            return Err(format!(
+                "Out of bounds margin must be between 0 and {MAX_MARGIN}"
+            ));
+        }
+
+        if !(1..=MAX_TICKS).contains(&max_ticks) {
+            return Err(format!(
+                "Maximum flight duration must be between 1 and {MAX_TICKS} ticks"
+            ));
+        }
+
+        Ok(Self::new(margin, max_ticks))
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Everything about the probe that decides where it goes next, assuming it can't thrust
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeState {
+    pub pos: Vec2F,
+    pub velocity: Vec2F,
+    /// The wormhole end the probe can't go back into yet
+    pub wormhole_exit: Option<(usize, usize)>,
+    /// How many checkpoints have been passed. This only ever grows, so an unchanged count means
+    /// the same checkpoints
+    pub checkpoints: usize,
+}
+
+/// Detects when the probe returns to a state it has already been in, using Brent's algorithm.
+/// Since the simulation is deterministic, an exact repeat means the flight will loop forever
+#[derive(Debug, Clone)]
+pub struct OrbitDetector {
+    saved: ProbeState,
+    power: u32,
+    length: u32,
+}
+
+impl OrbitDetector {
+    pub const fn new(state: ProbeState) -> Self {
+        Self {
+            saved: state,
+            power: 1,
+            length: 1,
+        }
+    }
+
+    /// Feed the next state of the probe. Returns the period of the orbit once a repeat is found
+    pub fn step(&mut self, state: ProbeState) -> Option<u32> {
+        if self.saved == state {
+            return Some(self.length);
+        }
+
+        if self.power == self.length {
+            self.saved = state;
+            self.power *= 2;
+            self.length = 0;
+        }
+
+        self.length += 1;
+
+        None
+    }
+}
@@ -0,0 +1,363 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use super::{Planet, Vec2F};
+
+/// How deep orbits around orbiting planets can go before giving up, in case of loops
+const MAX_ANCHOR_DEPTH: u32 = 8;
+
+/// What an orbiting planet goes around
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Point(Vec2F),
+    /// The index of another planet, which may be moving itself
+    Planet(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Motion {
+    Static,
+    /// Circle around the anchor once every `period` ticks, starting `phase` radians around
+    Orbit {
+        anchor: Anchor,
+        radius: f64,
+        period: f64,
+        phase: f64,
+    },
+    /// Loop through the waypoints at a constant speed, taking `period` ticks to get back to the
+    /// start. The planet's own position is the first waypoint
+    Path {
+        waypoints: Vec<Vec2F>,
+        period: f64,
+    },
+}
+
+impl Motion {
+    pub const fn period(&self) -> Option<f64> {
+        match self {
+            Self::Static => None,
+            Self::Orbit { period, .. } | Self::Path { period, .. } => Some(*period),
+        }
+    }
+
+    pub const fn set_period(&mut self, new_period: f64) {
+        if let Self::Orbit { period, .. } | Self::Path { period, .. } = self {
+            *period = new_period.max(10.0);
+        }
+    }
+
+    /// Parse a motion from the level file. Returns the motion and the index of the planet it
+    /// belongs to
+    pub fn from_nums(
+        pop: &mut impl FnMut() -> Result<f64, String>,
+    ) -> Result<(usize, Self), String> {
+        let planet = pop()? as usize;
+        let kind = pop()? as u32;
+        let period = pop()?;
+
+        // A period of zero would divide by zero, and a negative one would run the motion
+        // backwards, which the editor can't make
+        if !(period.is_finite() && period > 0.0) {
+            return Err(format!("Planet {planet} has a motion period of {period}"));
+        }
+
+        let motion = match kind {
+            1 | 2 => {
+                let phase = pop()?;
+                let radius = pop()?;
+
+                let anchor = if kind == 1 {
+                    Anchor::Point(Vec2F::new(pop()?, pop()?))
+                } else {
+                    Anchor::Planet(pop()? as usize)
+                };
+
+                Self::Orbit {
+                    anchor,
+                    radius,
+                    period,
+                    phase,
+                }
+            }
+
+            3 => {
+                let mut waypoints = Vec::new();
+                for _ in 0..pop()? as usize {
+                    waypoints.push(Vec2F::new(pop()?, pop()?));
+                }
+
+                Self::Path { waypoints, period }
+            }
+
+            _ => return Err(format!("Unknown motion type {kind}")),
+        };
+
+        Ok((planet, motion))
+    }
+
+    /// Write a motion for the level file, in the order [`Motion::from_nums`] reads it
+    pub fn to_nums(&self, planet: usize) -> Option<String> {
+        match self {
+            Self::Static => None,
+
+            Self::Orbit {
+                anchor: Anchor::Point(centre),
+                radius,
+                period,
+                phase,
+            } => Some(format!("{planet} 1 {period} {phase} {radius} {centre}")),
+
+            Self::Orbit {
+                anchor: Anchor::Planet(other),
+                radius,
+                period,
+                phase,
+            } => Some(format!("{planet} 2 {period} {phase} {radius} {other}")),
+
+            Self::Path { waypoints, period } => {
+                let points: Vec<String> = waypoints[1..].iter().map(ToString::to_string).collect();
+
+                Some(format!(
+                    "{planet} 3 {period} {} {}",
+                    points.len(),
+                    points.join(" ")
+                ))
+            }
+        }
+    }
+}
+
+/// How a wall moves. Every motion is relative to where the wall starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallMotion {
+    Static,
+    /// Slide `offset` away at `speed` pixels per tick, then jump back to the start (or slide back,
+    /// if it ping-pongs)
+    Slide {
+        offset: Vec2F,
+        speed: f64,
+        ping_pong: bool,
+    },
+    /// Turn around `pivot` at `speed` radians per tick. Walls that ping-pong swing through `sweep`
+    /// radians and back, the rest keep turning
+    Rotate {
+        pivot: Vec2F,
+        speed: f64,
+        sweep: f64,
+        ping_pong: bool,
+    },
+}
+
+impl WallMotion {
+    /// Cycle between standing still, sliding and rotating around `centre`
+    pub const fn next(self, centre: Vec2F) -> Self {
+        match self {
+            Self::Static => Self::Slide {
+                offset: Vec2F::new(0.0, 40.0),
+                speed: 0.5,
+                ping_pong: true,
+            },
+            Self::Slide { .. } => Self::Rotate {
+                pivot: centre,
+                speed: 0.02,
+                sweep: FRAC_PI_2,
+                ping_pong: false,
+            },
+            Self::Rotate { .. } => Self::Static,
+        }
+    }
+
+    /// Speed up by `steps` notches. Rotating walls turn the other way when slowed past zero
+    pub const fn change_speed(&mut self, steps: f64) {
+        match self {
+            Self::Static => (),
+            Self::Slide { speed, .. } => *speed = steps.mul_add(0.1, *speed).max(0.0),
+            Self::Rotate { speed, .. } => *speed = steps.mul_add(0.005, *speed),
+        }
+    }
+
+    /// Widen the swing of a rotating wall by `steps` notches of 15 degrees
+    pub fn change_sweep(&mut self, steps: f64) {
+        if let Self::Rotate { sweep, .. } = self {
+            *sweep = steps.mul_add(PI / 12.0, *sweep).clamp(PI / 12.0, TAU);
+        }
+    }
+
+    pub const fn toggle_ping_pong(&mut self) {
+        if let Self::Slide { ping_pong, .. } | Self::Rotate { ping_pong, .. } = self {
+            *ping_pong = !*ping_pong;
+        }
+    }
+
+    /// Where a point on the wall that starts at `pos` is after `time` ticks
+    pub fn transform(&self, pos: Vec2F, time: f64) -> Vec2F {
+        match *self {
+            Self::Static => pos,
+
+            Self::Slide {
+                offset,
+                speed,
+                ping_pong,
+            } => pos + offset * progress(offset.magnitude(), speed * time, ping_pong),
+
+            Self::Rotate {
+                pivot,
+                speed,
+                sweep,
+                ping_pong,
+            } => {
+                let angle = if ping_pong {
+                    sweep * progress(sweep, speed.abs() * time, true) * speed.signum()
+                } else {
+                    speed * time
+                };
+
+                pivot + (pos - pivot).rotated(angle)
+            }
+        }
+    }
+
+    /// Whether the wall jumps back to the start between `time` and `time + dt`, rather than
+    /// moving smoothly
+    pub fn jumps(&self, time: f64, dt: f64) -> bool {
+        match *self {
+            Self::Slide {
+                offset,
+                speed,
+                ping_pong: false,
+            } => {
+                let distance = offset.magnitude();
+                progress(distance, speed * (time + dt), false)
+                    < progress(distance, speed * time, false)
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Parse a wall motion from the level file. Returns the motion and the index of the wall it
+    /// belongs to
+    pub fn from_nums(
+        pop: &mut impl FnMut() -> Result<f64, String>,
+    ) -> Result<(usize, Self), String> {
+        let wall = pop()? as usize;
+        let kind = pop()? as u32;
+        let speed = pop()?;
+        let ping_pong = pop()? != 0.0;
+
+        let motion = match kind {
+            1 => Self::Slide {
+                offset: Vec2F::new(pop()?, pop()?),
+                speed,
+                ping_pong,
+            },
+
+            2 => Self::Rotate {
+                sweep: pop()?,
+                pivot: Vec2F::new(pop()?, pop()?),
+                speed,
+                ping_pong,
+            },
+
+            _ => return Err(format!("Unknown wall motion type {kind}")),
+        };
+
+        Ok((wall, motion))
+    }
+
+    /// Write a wall motion for the level file, in the order [`WallMotion::from_nums`] reads it
+    pub fn to_nums(self, wall: usize) -> Option<String> {
+        match self {
+            Self::Static => None,
+
+            Self::Slide {
+                offset,
+                speed,
+                ping_pong,
+            } => Some(format!("{wall} 1 {speed} {} {offset}", u8::from(ping_pong))),
+
+            Self::Rotate {
+                pivot,
+                speed,
+                sweep,
+                ping_pong,
+            } => Some(format!(
+                "{wall} 2 {speed} {} {sweep} {pivot}",
+                u8::from(ping_pong)
+            )),
+        }
+    }
+}
+
+/// How far through a motion of length `distance` something is after travelling `travelled`, from
+/// 0 to 1. Ping-ponging motions go back down to 0 rather than starting over
+fn progress(distance: f64, travelled: f64, ping_pong: bool) -> f64 {
+    if distance <= 0.0 {
+        return 0.0;
+    }
+
+    let laps = travelled / distance;
+
+    if ping_pong {
+        1.0 - (laps.rem_euclid(2.0) - 1.0).abs()
+    } else {
+        laps.rem_euclid(1.0)
+    }
+}
+
+/// Where planet `index` is after `time` ticks
+pub fn position_at(planets: &[Planet], index: usize, time: f64) -> Vec2F {
+    position_at_depth(planets, index, time, 0)
+}
+
+fn position_at_depth(planets: &[Planet], index: usize, time: f64, depth: u32) -> Vec2F {
+    let planet = &planets[index];
+
+    match &planet.motion {
+        Motion::Static => planet.pos,
+
+        Motion::Orbit {
+            anchor,
+            radius,
+            period,
+            phase,
+        } => {
+            let centre = match anchor {
+                Anchor::Point(centre) => *centre,
+                Anchor::Planet(other) if depth < MAX_ANCHOR_DEPTH && *other < planets.len() => {
+                    position_at_depth(planets, *other, time, depth + 1)
+                }
+                Anchor::Planet(_) => return planet.pos,
+            };
+
+            let angle = TAU * time / period + phase;
+
+            centre + Vec2F::new(angle.cos(), angle.sin()) * *radius
+        }
+
+        Motion::Path { waypoints, period } => along_path(waypoints, time / period),
+    }
+}
+
+/// The point `fraction` of the way around the closed loop through `waypoints`
+fn along_path(waypoints: &[Vec2F], fraction: f64) -> Vec2F {
+    let legs = waypoints
+        .iter()
+        .zip(waypoints.iter().cycle().skip(1))
+        .map(|(from, to)| (*from, *to, (*to - *from).magnitude()));
+
+    let total: f64 = legs.clone().map(|(_, _, length)| length).sum();
+    if total == 0.0 {
+        return waypoints[0];
+    }
+
+    let mut remaining = fraction.rem_euclid(1.0) * total;
+    for (from, to, length) in legs {
+        if remaining <= length {
+            return from + (to - from) * (remaining / length);
+        }
+
+        remaining -= length;
+    }
+
+    waypoints[0]
+}
@@ -0,0 +1,185 @@
+use std::{fs, path::Path};
+
+use super::{Cause, LevelData, Limits, PhysicsProfile, Simulation, Vec2F};
+
+/// The default distance a simulated position may be from a snapshotted one
+const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+/// How a snapshotted flight ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordedOutcome {
+    Crashed,
+    Won,
+    /// The snapshot was stopped while the probe was still flying
+    Flying,
+}
+
+/// A regression snapshot of a flight through the editor's parity simulation: the launch velocity
+/// going in, and the position of the probe after every tick coming out. These were taken from
+/// the editor, not recorded in the game, so they catch changes to the parity physics rather than
+/// prove it matches the game
+#[derive(Debug, Clone)]
+pub struct Recording {
+    /// The path to the level, relative to the snapshot
+    pub level: String,
+    /// The version of the game whose physics the snapshot was taken with, as named in
+    /// `GAME_VERSIONS`
+    pub version: String,
+    pub launch: Vec2F,
+    pub tolerance: f64,
+    pub outcome: RecordedOutcome,
+    pub positions: Vec<Vec2F>,
+}
+
+impl Recording {
+    pub fn load(filepath: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(filepath)
+            .map_err(|_| format!("Failed to open {}", filepath.display()))?;
+
+        let mut level = None;
+        let mut version = String::from("latest");
+        let mut launch = None;
+        let mut tolerance = DEFAULT_TOLERANCE;
+        let mut outcome = RecordedOutcome::Flying;
+        let mut positions = Vec::new();
+
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(metadata) = line.strip_prefix("#!") {
+                let mut words = metadata.split_whitespace();
+
+                match (words.next(), words.next(), words.next()) {
+                    (Some("level"), Some(path), _) => level = Some(String::from(path)),
+                    (Some("version"), Some(name), _) => version = String::from(name),
+                    (Some("launch"), Some(x), Some(y)) => launch = Some(parse_vec(x, y)?),
+                    (Some("tolerance"), Some(t), _) => {
+                        tolerance = t.parse().map_err(|_| "Invalid tolerance")?;
+                    }
+                    (Some("outcome"), Some("crashed"), _) => outcome = RecordedOutcome::Crashed,
+                    (Some("outcome"), Some("won"), _) => outcome = RecordedOutcome::Won,
+                    (Some("outcome"), Some("flying"), _) => outcome = RecordedOutcome::Flying,
+                    _ => return Err(format!("Invalid snapshot metadata `{metadata}`")),
+                }
+            } else if !line.starts_with('#') {
+                let mut words = line.split_whitespace();
+                let (Some(x), Some(y)) = (words.next(), words.next()) else {
+                    return Err(format!("Invalid position `{line}`"));
+                };
+
+                positions.push(parse_vec(x, y)?);
+            }
+        }
+
+        Ok(Self {
+            level: level.ok_or("Recording has no level")?,
+            version,
+            launch: launch.ok_or("Recording has no launch velocity")?,
+            tolerance,
+            outcome,
+            positions,
+        })
+    }
+
+    /// Fly the snapshotted launch through the editor's parity simulation, returning a description
+    /// of the first tick where it strays from the snapshot
+    pub fn check(&self, level_data: &LevelData) -> Result<(), String> {
+        let mut physics = PhysicsProfile::for_version(&self.version)
+            .ok_or_else(|| format!("Unknown game version `{}`", self.version))?;
+        physics.parity = true;
+
+        let mut simulation = Simulation::empty();
+        simulation.push(level_data);
+        simulation.physics = physics;
+        simulation.limits = Limits::new(f64::INFINITY, u32::MAX);
+        simulation.player.velocity = self.launch;
+
+        let last_tick = self.positions.len();
+
+        for (tick, expected) in (1..).zip(&self.positions) {
+            let event = simulation.tick();
+            let found = simulation.player.pos;
+
+            if (found - *expected).magnitude() > self.tolerance {
+                return Err(format!(
+                    "Tick {tick}: expected the probe at ({expected}), found it at ({found})"
+                ));
+            }
+
+            match (event.map(|e| e.cause), tick == last_tick) {
+                (None, false) => (),
+                (None, true) if self.outcome == RecordedOutcome::Flying => (),
+                (None, true) => return Err(format!("Tick {tick}: the probe is still flying")),
+                (Some(cause), true) if self.outcome == outcome_of(cause) => (),
+                (Some(cause), _) => {
+                    return Err(format!(
+                        "Tick {tick}: the flight ended early with {cause:?}"
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+const fn outcome_of(cause: Cause) -> RecordedOutcome {
+    match cause {
+        Cause::Target => RecordedOutcome::Won,
+        Cause::Planet(_) | Cause::Wall(_) => RecordedOutcome::Crashed,
+        Cause::OutOfBounds | Cause::Timeout | Cause::Orbit(_) => RecordedOutcome::Flying,
+    }
+}
+
+fn parse_vec(x: &str, y: &str) -> Result<Vec2F, String> {
+    match (x.parse(), y.parse()) {
+        (Ok(x), Ok(y)) => Ok(Vec2F::new(x, y)),
+        _ => Err(format!("`{x} {y}` is not a pair of numbers")),
+    }
+}
+
+/// The path of each snapshot checked, along with whether it matched
+pub type CorpusResults = Vec<(String, Result<(), String>)>;
+
+/// Check every snapshot (`.trj` file) in a directory, returning the result for each one
+pub fn check_corpus(dir: &Path) -> Result<CorpusResults, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map_err(|_| format!("Failed to open {}", dir.display()))?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "trj"))
+        .collect();
+    paths.sort();
+
+    let results = paths
+        .into_iter()
+        .map(|path| {
+            let result = Recording::load(&path).and_then(|recording| {
+                let level_path = dir.join(&recording.level);
+                let level_data = LevelData::load(&level_path.to_string_lossy())?;
+
+                recording.check(&level_data)
+            });
+
+            (path.display().to_string(), result)
+        })
+        .collect();
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    #[test]
+    fn regression_snapshots_still_match() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("parity");
+        let results = super::check_corpus(&dir).expect("the snapshot folder exists");
+
+        assert!(!results.is_empty(), "the snapshot folder has no flights");
+
+        for (path, result) in results {
+            if let Err(e) = result {
+                panic!("{path} no longer matches its snapshot: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Display;
+
+use super::Integrator;
+
+/// The constants the simulation runs on. The game's have to be matched exactly, or the editor's
+/// trajectories won't be where the probe actually flies, but levels can pick other profiles to
+/// prototype new mechanics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsProfile {
+    /// The gravitational constant
+    pub gravity: f64,
+    /// The probe crashes into a planet when it is closer than `mass / crash_radius_divisor`
+    pub crash_radius_divisor: f64,
+    /// The weakest the probe can be launched
+    pub min_power: f64,
+    /// The strongest the probe can be launched
+    pub max_power: f64,
+    pub integrator: Integrator,
+    /// Reproduce the game's collision checks exactly, rather than sweeping the probe's path.
+    /// This also ignores substeps and the integrator, since the game doesn't have them. Only the
+    /// `game` profile starts with it on
+    pub parity: bool,
+}
+
+impl PhysicsProfile {
+    /// The physics of the latest release of Spaceshipment
+    pub const GAME: Self = Self {
+        // gravity: 6.67430e-11,
+        gravity: 0.55,
+        crash_radius_divisor: 12.0,
+        min_power: 1.0,
+        max_power: 3.0,
+        integrator: Integrator::SemiImplicitEuler,
+        parity: true,
+    };
+
+    /// Look up the physics of a specific version of the game
+    pub fn for_version(version: &str) -> Option<Self> {
+        GAME_VERSIONS
+            .iter()
+            .find(|(name, _)| *name == version)
+            .map(|(_, physics)| *physics)
+    }
+
+    /// The square of the distance from the centre of a planet of `mass` at which the probe crashes
+    pub fn crash_radius_squared(&self, mass: f64) -> f64 {
+        mass.powi(2) / self.crash_radius_divisor.powi(2)
+    }
+
+    pub const fn clamp_power(&self, power: f64) -> f64 {
+        power.clamp(self.min_power, self.max_power)
+    }
+
+    /// The name of the built in profile with these constants, if there is one
+    pub fn name(&self) -> Option<&'static str> {
+        PROFILES
+            .iter()
+            .find(|(_, p)| {
+                Self {
+                    parity: p.parity,
+                    ..*self
+                } == *p
+            })
+            .map(|(name, _)| *name)
+    }
+
+    /// Whether these constants run in parity mode unless a level says otherwise, which is only
+    /// the case for the `game` profile
+    pub fn default_parity(&self) -> bool {
+        self.name()
+            .and_then(|name| PROFILES.iter().find(|(n, _)| *n == name))
+            .is_some_and(|(_, profile)| profile.parity)
+    }
+
+    /// Use the next integrator, keeping every other constant the same. Parity mode would ignore
+    /// the choice, so it's turned off
+    pub const fn cycle_integrator(&mut self) {
+        self.integrator = self.integrator.next();
+        self.parity = false;
+    }
+
+    /// Switch to the built in profile after this one, with parity mode on only for `game`
+    pub fn cycle(&mut self) {
+        let next = self
+            .name()
+            .and_then(|name| PROFILES.iter().position(|(n, _)| *n == name))
+            .map_or(0, |i| (i + 1) % PROFILES.len());
+
+        *self = PROFILES[next].1;
+    }
+
+    /// Parse the arguments of a `physics` metadata line, either the name of a built in profile or
+    /// `custom` followed by each constant
+    pub fn from_words<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Self, String> {
+        let name = words.next().ok_or("Missing physics profile")?;
+
+        if name != "custom" {
+            return PROFILES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, profile)| *profile)
+                .ok_or_else(|| format!("Unknown physics profile `{name}`"));
+        }
+
+        let mut number = || {
+            words
+                .next()
+                .and_then(|w| w.parse::<f64>().ok())
+                .ok_or("Missing physics constant")
+        };
+
+        let gravity = number()?;
+        let crash_radius_divisor = number()?;
+        let min_power = number()?;
+        let max_power = number()?;
+        let integrator = Integrator::from_name(words.next().ok_or("Missing integrator")?)?;
+
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+        if !positive(gravity) {
+            return Err(format!("Gravity must be above 0, not {gravity}"));
+        }
+        if !positive(crash_radius_divisor) {
+            return Err(format!(
+                "Crash radius divisor must be above 0, not {crash_radius_divisor}"
+            ));
+        }
+        if !positive(min_power) {
+            return Err(format!(
+                "Minimum launch power must be above 0, not {min_power}"
+            ));
+        }
+        if !positive(max_power) {
+            return Err(format!(
+                "Maximum launch power must be above 0, not {max_power}"
+            ));
+        }
+        if min_power > max_power {
+            return Err(String::from("Minimum launch power is above the maximum"));
+        }
+
+        Ok(Self {
+            gravity,
+            crash_radius_divisor,
+            min_power,
+            max_power,
+            integrator,
+            parity: false,
+        })
+    }
+}
+
+impl Default for PhysicsProfile {
+    fn default() -> Self {
+        Self::GAME
+    }
+}
+
+impl Display for PhysicsProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = self.name() {
+            return write!(f, "{name}");
+        }
+
+        write!(
+            f,
+            "custom {} {} {} {} {}",
+            self.gravity,
+            self.crash_radius_divisor,
+            self.min_power,
+            self.max_power,
+            self.integrator.name()
+        )
+    }
+}
+
+/// Every version of the game with different physics. Add an entry here when a game update
+/// changes any of the constants, so levels and recordings can be checked against the right one
+pub const GAME_VERSIONS: &[(&str, PhysicsProfile)] = &[("latest", PhysicsProfile::GAME)];
+
+/// The profiles that can be picked in the editor
+pub const PROFILES: &[(&str, PhysicsProfile)] = &[
+    ("game", PhysicsProfile::GAME),
+    (
+        "low-gravity",
+        PhysicsProfile {
+            gravity: 0.3,
+            parity: false,
+            ..PhysicsProfile::GAME
+        },
+    ),
+    (
+        "high-gravity",
+        PhysicsProfile {
+            gravity: 1.0,
+            parity: false,
+            ..PhysicsProfile::GAME
+        },
+    ),
+    (
+        "strong-launch",
+        PhysicsProfile {
+            max_power: 5.0,
+            parity: false,
+            ..PhysicsProfile::GAME
+        },
+    ),
+    (
+        "small-crash",
+        PhysicsProfile {
+            crash_radius_divisor: 18.0,
+            parity: false,
+            ..PhysicsProfile::GAME
+        },
+    ),
+];
@@ -1,44 +1,179 @@
-use super::Vec2F;
+use super::{Motion, PhysicsProfile, Vec2F};
 
-#[derive(Debug, Clone)]
+/// The heaviest a planet can be scrolled to. Planets larger than this look funky
+pub const MAX_PLANET_MASS: f64 = 12000.0;
+
+/// How many times harder a black hole pulls than a normal planet of the same mass
+const BLACK_HOLE_PULL: f64 = 4.0;
+
+/// What a planet does to the probe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanetKind {
+    /// Pulls the probe in, and crashes it within `mass / crash_radius_divisor`
+    Normal,
+    /// Pushes the probe away, but still crashes it like a normal planet. The game stores these
+    /// as planets with negative mass
+    Anti,
+    /// Pulls much harder than a normal planet, and only crashes the probe once it's inside the
+    /// event horizon, a circle of radius `horizon` pixels no matter the mass
+    BlackHole { horizon: f64 },
+    /// Pulls like a normal planet, but the probe flies straight through it
+    Ghost,
+}
+
+impl PlanetKind {
+    /// Cycle between normal, anti, black hole and ghost planets
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Normal => Self::Anti,
+            Self::Anti => Self::BlackHole { horizon: 6.0 },
+            Self::BlackHole { .. } => Self::Ghost,
+            Self::Ghost => Self::Normal,
+        }
+    }
+
+    /// Toggle between normal and anti planets, leaving other kinds as they are
+    pub const fn inverted(self) -> Self {
+        match self {
+            Self::Normal => Self::Anti,
+            Self::Anti => Self::Normal,
+            kind => kind,
+        }
+    }
+
+    /// Grow a black hole's event horizon by `steps` pixels
+    pub fn change_horizon(&mut self, steps: f64) {
+        if let Self::BlackHole { horizon } = self {
+            *horizon = (*horizon + steps).clamp(1.0, 60.0);
+        }
+    }
+
+    /// Parse a planet kind from the level file. Normal and anti planets are told apart by the
+    /// sign of their mass instead, so only the other kinds are listed. Returns the kind and the
+    /// index of the planet it belongs to
+    pub fn from_nums(
+        pop: &mut impl FnMut() -> Result<f64, String>,
+    ) -> Result<(usize, Self), String> {
+        let planet = pop()? as usize;
+
+        let kind = match pop()? as u32 {
+            1 => Self::BlackHole { horizon: pop()? },
+            2 => Self::Ghost,
+            kind => return Err(format!("Unknown planet type {kind}")),
+        };
+
+        Ok((planet, kind))
+    }
+
+    /// Write a planet kind for the level file, in the order [`PlanetKind::from_nums`] reads it
+    pub fn to_nums(self, planet: usize) -> Option<String> {
+        match self {
+            Self::Normal | Self::Anti => None,
+            Self::BlackHole { horizon } => Some(format!("{planet} 1 {horizon}")),
+            Self::Ghost => Some(format!("{planet} 2")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Planet {
+    /// How big the planet is. This is always positive, and `kind` decides which way it pulls
     pub mass: f64,
+    pub kind: PlanetKind,
+    /// Where the planet is. For moving planets in the editor, this is where it starts
     pub pos: Vec2F,
+    pub motion: Motion,
 }
 
 impl Planet {
     pub const fn new(mass: f64, pos: Vec2F) -> Self {
-        Self { mass, pos }
+        Self {
+            mass,
+            kind: PlanetKind::Normal,
+            pos,
+            motion: Motion::Static,
+        }
     }
 
+    /// Read a planet as the game stores it, where anti-planets have negative mass
     pub const fn from_nums(nums: &[f64]) -> Self {
         assert!(
             nums.len() == 3,
             "Wrong number of items passed to `Player::from_nums`"
         );
 
-        Self::new(nums[0], Vec2F::new(nums[1], nums[2]))
+        let mut planet = Self::new(nums[0].abs(), Vec2F::new(nums[1], nums[2]));
+        if nums[0].is_sign_negative() {
+            planet.kind = PlanetKind::Anti;
+        }
+
+        planet
     }
 
-    pub fn change_size(&mut self, change: f64) {
-        if self.mass.is_sign_positive() {
-            self.mass *= 1.0 + change;
-            self.mass = self.mass.min(12000.0); // planets larger than 12000 look funky
-
-            if self.mass < 50.0 {
-                self.mass = -50.0;
-            } else {
-                self.mass = self.mass.max(50.0);
-            }
-        } else {
-            self.mass *= 1.0 - change;
-            self.mass = self.mass.max(-12000.0);
-
-            if self.mass > -50.0 {
-                self.mass = 50.0;
-            } else {
-                self.mass = self.mass.min(-50.0);
-            }
+    /// The mass as the game stores it, negative for anti-planets
+    pub fn signed_mass(&self) -> f64 {
+        match self.kind {
+            PlanetKind::Anti => -self.mass,
+            _ => self.mass,
+        }
+    }
+
+    /// The mass the planet pulls with, which is negative if it pushes instead
+    pub fn pull(&self) -> f64 {
+        match self.kind {
+            PlanetKind::Normal | PlanetKind::Ghost => self.mass,
+            PlanetKind::Anti => -self.mass,
+            PlanetKind::BlackHole { .. } => self.mass * BLACK_HOLE_PULL,
+        }
+    }
+
+    /// The square of how close the probe can get before crashing, or `None` if it can't crash
+    /// into the planet at all
+    pub fn crash_radius_squared(&self, physics: &PhysicsProfile) -> Option<f64> {
+        match self.kind {
+            PlanetKind::Normal | PlanetKind::Anti => Some(physics.crash_radius_squared(self.mass)),
+            PlanetKind::BlackHole { horizon } => Some(horizon.powi(2)),
+            PlanetKind::Ghost => None,
         }
     }
+
+    /// How big the planet is drawn, and how close the mouse has to be to grab it
+    pub fn radius(&self) -> f64 {
+        self.mass / 12.0
+    }
+
+    /// Returns the fraction of the way along the path from `from` to `to` at which the probe
+    /// first enters the planet's crash radius, if it does at all
+    pub fn time_of_impact(&self, from: Vec2F, to: Vec2F, physics: &PhysicsProfile) -> Option<f64> {
+        let path = to - from;
+        let offset = from - self.pos;
+        let radius_squared = self.crash_radius_squared(physics)?;
+
+        let c = offset.dot(offset) - radius_squared;
+        if c < 0.0 {
+            return Some(0.0);
+        }
+
+        let a = path.dot(path);
+        if a == 0.0 {
+            return None;
+        }
+
+        let b = 2.0 * offset.dot(path);
+        let discriminant = b.mul_add(b, -4.0 * a * c);
+
+        // A path that only grazes the edge doesn't count, just like a probe sitting exactly on it
+        if discriminant <= 0.0 {
+            return None;
+        }
+
+        let time = (-b - discriminant.sqrt()) / (2.0 * a);
+
+        (0.0..=1.0).contains(&time).then_some(time)
+    }
+
+    /// Grow the planet by `change` times its mass. This never changes what kind of planet it is
+    pub fn change_size(&mut self, change: f64) {
+        self.mass = (self.mass * (1.0 + change)).clamp(50.0, MAX_PLANET_MASS);
+    }
 }
@@ -1,7 +1,7 @@
 use std::f64::consts;
-use super::Vec2F;
+use super::{PhysicsProfile, Vec2F};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Player {
     pub pos: Vec2F,
     pub velocity: Vec2F,
@@ -15,13 +15,13 @@ impl Player {
         }
     }
 
-    pub fn aim_at(&mut self, target: Vec2F) {
+    pub fn aim_at(&mut self, target: Vec2F, physics: &PhysicsProfile) {
         let distance_to_other = target - self.pos;
 
         let normalised = distance_to_other.normalised();
 
         let power = (distance_to_other.magnitude() - 30.0) / 30.0;
-        let clamped_power = power.clamp(1.0, 3.0);
+        let clamped_power = physics.clamp_power(power);
 
         self.velocity = normalised * clamped_power;
     }
@@ -35,11 +35,11 @@ impl Player {
     }
 
     /// Change the magnitude of the velocity by `change`
-    pub fn change_power(&mut self, change: f64) {
+    pub fn change_power(&mut self, change: f64, physics: &PhysicsProfile) {
         let normal = self.velocity.normalised();
 
         let power = self.velocity.magnitude() + change;
-        let clamped_power = power.clamp(1.0, 3.0);
+        let clamped_power = physics.clamp_power(power);
 
         self.velocity = normal * clamped_power;
     }
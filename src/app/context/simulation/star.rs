@@ -0,0 +1,22 @@
+use super::Vec2F;
+
+/// An optional pickup. Collecting stars adds to the score of a flight
+#[derive(Debug, Clone, PartialEq)]
+pub struct Star {
+    pub pos: Vec2F,
+}
+
+impl Star {
+    /// How close the probe has to get to collect a star
+    pub const RADIUS: f64 = 6.0;
+    /// The points each star is worth
+    pub const POINTS: u32 = 100;
+
+    pub const fn new(pos: Vec2F) -> Self {
+        Self { pos }
+    }
+
+    pub fn contains(&self, pos: Vec2F) -> bool {
+        (self.pos - pos).magnitude() < Self::RADIUS
+    }
+}
@@ -1,6 +1,6 @@
 use super::Vec2F;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Target {
     pub size: f64,
     pub pos: Vec2F,
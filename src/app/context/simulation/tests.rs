@@ -0,0 +1,873 @@
+use crate::app::context::{AppState, LevelData};
+
+use super::{
+    position_at, Anchor, Burn, Cause, Checkpoint, Integrator, Limits, Motion, PhysicsProfile,
+    Planet, PlanetKind, Simulation, Star, ThrustInput, Vec2F, Wall, WallKind, WallMotion, Wormhole,
+    Zone, ZoneEffect, THRUST,
+};
+
+const EPSILON: f64 = 1e-9;
+
+/// A simulation with the editor's swept collisions and substeps, and the target moved out of the
+/// way
+fn simulation(pos: Vec2F, velocity: Vec2F) -> Simulation {
+    let mut simulation = Simulation::empty();
+    simulation.physics.parity = false;
+    simulation.target.pos = Vec2F::new(-1000.0, -1000.0);
+    simulation.limits.margin = 1000.0;
+    simulation.player.pos = pos;
+    simulation.player.velocity = velocity;
+    simulation.orbit_detector = super::OrbitDetector::new(simulation.probe_state());
+
+    simulation
+}
+
+#[test]
+fn wall_hit_in_the_middle() {
+    let wall = Wall::new(Vec2F::new(200.0, 100.0), Vec2F::new(200.0, 140.0));
+
+    let time = wall
+        .intersects(Vec2F::new(150.0, 120.0), Vec2F::new(250.0, 120.0), 0.0, 1.0)
+        .expect("path crosses the wall");
+
+    assert!((time - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn wall_hit_exactly_on_its_end() {
+    let wall = Wall::new(Vec2F::new(200.0, 100.0), Vec2F::new(200.0, 140.0));
+
+    let time = wall
+        .intersects(Vec2F::new(150.0, 100.0), Vec2F::new(250.0, 100.0), 0.0, 1.0)
+        .expect("path touches the end of the wall");
+
+    assert!((time - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn wall_hit_along_its_length() {
+    let wall = Wall::new(Vec2F::new(100.0, 50.0), Vec2F::new(200.0, 50.0));
+
+    let time = wall
+        .intersects(Vec2F::new(50.0, 50.0), Vec2F::new(150.0, 50.0), 0.0, 1.0)
+        .expect("path runs along the wall");
+
+    assert!((time - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn wall_near_miss() {
+    let wall = Wall::new(Vec2F::new(200.0, 100.0), Vec2F::new(200.0, 140.0));
+
+    assert!(wall
+        .intersects(Vec2F::new(150.0, 99.99), Vec2F::new(250.0, 99.99), 0.0, 1.0)
+        .is_none());
+    assert!(wall
+        .intersects(
+            Vec2F::new(150.0, 120.0),
+            Vec2F::new(199.99, 120.0),
+            0.0,
+            1.0
+        )
+        .is_none());
+}
+
+#[test]
+fn planet_hit_between_samples() {
+    let planet = Planet::new(120.0, Vec2F::new(50.0, 0.0));
+
+    let time = planet
+        .time_of_impact(
+            Vec2F::new(0.0, 0.0),
+            Vec2F::new(100.0, 0.0),
+            &PhysicsProfile::GAME,
+        )
+        .expect("path passes through the planet");
+
+    assert!((time - 0.4).abs() < EPSILON);
+}
+
+#[test]
+fn planet_near_miss() {
+    let planet = Planet::new(120.0, Vec2F::new(50.0, 0.0));
+
+    assert!(planet
+        .time_of_impact(
+            Vec2F::new(0.0, 10.01),
+            Vec2F::new(100.0, 10.01),
+            &PhysicsProfile::GAME
+        )
+        .is_none());
+    assert!(planet
+        .time_of_impact(
+            Vec2F::new(0.0, 9.99),
+            Vec2F::new(100.0, 9.99),
+            &PhysicsProfile::GAME
+        )
+        .is_some());
+    // Stops just short of the planet
+    assert!(planet
+        .time_of_impact(
+            Vec2F::new(0.0, 0.0),
+            Vec2F::new(39.99, 0.0),
+            &PhysicsProfile::GAME
+        )
+        .is_none());
+}
+
+/// Fly until the flight ends, or give up after `ticks` ticks
+fn fly(simulation: &mut Simulation, ticks: u32) -> Option<super::Event> {
+    (0..ticks).find_map(|_| simulation.tick())
+}
+
+#[test]
+fn crashes_record_which_planet_and_where() {
+    let mut simulation = simulation(Vec2F::new(50.0, 120.0), Vec2F::new(10.0, 0.0));
+    simulation.planets = vec![
+        Planet::new(60.0, Vec2F::new(350.0, 20.0)),
+        Planet::new(120.0, Vec2F::new(100.0, 120.0)),
+    ];
+
+    let event = fly(&mut simulation, 100).expect("the probe crashes");
+
+    assert_eq!(event.cause, Cause::Planet(1));
+    assert_eq!(event.tick, simulation.ticks);
+    assert_eq!(event.velocity, simulation.player.velocity);
+    // Stopped right on the edge of the crash radius
+    assert!(((event.pos - simulation.planets[1].pos).magnitude() - 10.0).abs() < 1e-6);
+}
+
+#[test]
+fn crashes_record_which_wall_and_when() {
+    let mut simulation = simulation(Vec2F::new(50.0, 120.0), Vec2F::new(10.0, 0.0));
+    simulation.walls = vec![
+        Wall::new(Vec2F::new(300.0, 0.0), Vec2F::new(300.0, 20.0)),
+        Wall::new(Vec2F::new(100.0, 100.0), Vec2F::new(100.0, 140.0)),
+    ];
+
+    let event = fly(&mut simulation, 100).expect("the probe crashes");
+
+    assert_eq!(event.cause, Cause::Wall(1));
+    assert_eq!(event.tick, 5);
+    assert!((event.pos - Vec2F::new(100.0, 120.0)).magnitude() < EPSILON);
+    assert_eq!(
+        AppState::GameOver(event).to_string(),
+        "Probe crashed into wall 1! Tick 5 at (100, 120)"
+    );
+}
+
+#[test]
+fn leaving_the_field_ends_the_flight() {
+    let mut simulation = simulation(Vec2F::new(390.0, 120.0), Vec2F::new(5.0, 0.0));
+    simulation.limits.margin = 0.0;
+
+    let event = fly(&mut simulation, 100).expect("the probe leaves");
+
+    assert_eq!(event.cause, Cause::OutOfBounds);
+    assert_eq!(event.tick, 3);
+}
+
+#[test]
+fn long_flights_time_out() {
+    let mut simulation = simulation(Vec2F::new(50.0, 120.0), Vec2F::new(0.1, 0.0));
+    simulation.limits.max_ticks = 100;
+
+    let event = fly(&mut simulation, 1000).expect("the flight times out");
+
+    assert_eq!(event.cause, Cause::Timeout);
+    assert_eq!(event.tick, 100);
+}
+
+#[test]
+fn a_probe_at_rest_is_in_orbit() {
+    let mut simulation = simulation(Vec2F::new(50.0, 120.0), Vec2F::ZERO);
+
+    let event = fly(&mut simulation, 100).expect("the orbit is found");
+
+    assert_eq!(event.cause, Cause::Orbit(1));
+    assert_eq!(event.tick, 1);
+}
+
+#[test]
+fn fuel_left_means_no_orbit() {
+    let mut simulation = simulation(Vec2F::new(50.0, 120.0), Vec2F::ZERO);
+    simulation.fuel = 10.0;
+    simulation.limits.max_ticks = 50;
+
+    let event = fly(&mut simulation, 100).expect("the flight times out");
+
+    assert_eq!(event.cause, Cause::Timeout);
+}
+
+#[test]
+fn substeps_catch_the_target_between_ticks() {
+    let fast = |substeps| {
+        let mut simulation = simulation(Vec2F::new(290.0, 120.0), Vec2F::new(80.0, 0.0));
+        simulation.target.pos = Vec2F::new(330.0, 120.0);
+        simulation.target.size = 10.0;
+        simulation.substeps = substeps;
+        simulation.tick().map(|event| event.cause)
+    };
+
+    assert_eq!(fast(4), Some(Cause::Target));
+    assert_eq!(fast(1), None);
+}
+
+#[test]
+fn limits_reject_unplayable_values() {
+    let parse = |line: &str| Limits::from_words(line.split_whitespace());
+
+    assert_eq!(parse("100 3000"), Ok(Limits::DEFAULT));
+    assert!(parse("-5 3000").is_err());
+    assert!(parse("inf 3000").is_err());
+    assert!(parse("NaN 3000").is_err());
+    assert!(parse("100 0").is_err());
+    assert!(parse("100 4294967295").is_err());
+}
+
+#[test]
+fn planet_kinds_crash_at_different_radii() {
+    let mut planet = Planet::new(120.0, Vec2F::new(50.0, 0.0));
+    let impact = |planet: &Planet| {
+        planet.time_of_impact(
+            Vec2F::new(0.0, 0.0),
+            Vec2F::new(100.0, 0.0),
+            &PhysicsProfile::GAME,
+        )
+    };
+
+    planet.kind = PlanetKind::BlackHole { horizon: 4.0 };
+    let time = impact(&planet).expect("path crosses the event horizon");
+    assert!((time - 0.46).abs() < EPSILON);
+    assert!(planet.pull() > planet.mass);
+
+    planet.kind = PlanetKind::Ghost;
+    assert!(impact(&planet).is_none());
+    assert!((planet.pull() - planet.mass).abs() < EPSILON);
+}
+
+#[test]
+fn scrolling_never_flips_a_planet() {
+    let mut planet = Planet::from_nums(&[-60.0, 0.0, 0.0]);
+    assert_eq!(planet.kind, PlanetKind::Anti);
+
+    planet.change_size(-0.5);
+    assert_eq!(planet.kind, PlanetKind::Anti);
+    assert!((planet.mass - 50.0).abs() < EPSILON);
+    assert!((planet.signed_mass() + 50.0).abs() < EPSILON);
+}
+
+#[test]
+fn fast_probe_does_not_tunnel_through_planet() {
+    let mut simulation = simulation(Vec2F::new(170.0, 120.0), Vec2F::new(60.0, 0.0));
+    simulation
+        .planets
+        .push(Planet::new(120.0, Vec2F::new(200.0, 120.0)));
+
+    // Both sampled positions (170 and ~230) are outside the planet's radius of 10
+    let event = simulation.tick().expect("probe crashes on the first tick");
+
+    assert_eq!(event.cause, Cause::Planet(0));
+    assert_eq!(event.tick, 1);
+    assert!((event.pos.x - 190.0).abs() < EPSILON);
+    assert!((event.pos.y - 120.0).abs() < EPSILON);
+}
+
+#[test]
+fn fast_probe_does_not_tunnel_through_wall_end() {
+    let mut simulation = simulation(Vec2F::new(100.0, 100.0), Vec2F::new(3.0, 0.0));
+    simulation.speed = 4;
+    simulation
+        .walls
+        .push(Wall::new(Vec2F::new(110.0, 100.0), Vec2F::new(110.0, 60.0)));
+
+    let mut event = None;
+    for _ in 0..10 {
+        event = simulation.tick();
+        if event.is_some() {
+            break;
+        }
+    }
+    let event = event.expect("probe hits the end of the wall");
+
+    assert_eq!(event.cause, Cause::Wall(0));
+    assert_eq!(event.tick, 4);
+    assert!((event.pos.x - 110.0).abs() < EPSILON);
+}
+
+#[test]
+fn near_miss_flies_past() {
+    let mut simulation = simulation(Vec2F::new(140.0, 109.0), Vec2F::new(120.0, 0.0));
+    simulation
+        .planets
+        .push(Planet::new(120.0, Vec2F::new(200.0, 120.0)));
+
+    // The closest approach is just under 11 pixels from the centre, outside the radius of 10
+    assert!(simulation.tick().is_none());
+    assert!(simulation.player.pos.x > 200.0);
+}
+
+#[test]
+fn substeps_keep_the_launch_speed() {
+    let mut simulation = simulation(Vec2F::new(100.0, 100.0), Vec2F::new(2.0, 1.0));
+    simulation.substeps = 4;
+
+    assert!(simulation.tick().is_none());
+    assert!((simulation.player.pos.x - 102.0).abs() < EPSILON);
+    assert!((simulation.player.pos.y - 101.0).abs() < EPSILON);
+}
+
+#[test]
+fn planets_orbit_other_planets() {
+    let mut sun = Planet::new(400.0, Vec2F::new(100.0, 100.0));
+    sun.motion = Motion::Orbit {
+        anchor: Anchor::Point(Vec2F::new(100.0, 50.0)),
+        radius: 50.0,
+        period: 100.0,
+        phase: std::f64::consts::FRAC_PI_2,
+    };
+    let mut moon = Planet::new(100.0, Vec2F::new(120.0, 100.0));
+    moon.motion = Motion::Orbit {
+        anchor: Anchor::Planet(0),
+        radius: 20.0,
+        period: 50.0,
+        phase: 0.0,
+    };
+    let planets = [sun, moon];
+
+    // A quarter of the sun's orbit and half of the moon's
+    let sun_pos = position_at(&planets, 0, 25.0);
+    let moon_pos = position_at(&planets, 1, 25.0);
+
+    assert!((sun_pos - Vec2F::new(50.0, 50.0)).magnitude() < EPSILON);
+    assert!((moon_pos - Vec2F::new(30.0, 50.0)).magnitude() < EPSILON);
+}
+
+#[test]
+fn paths_loop_at_constant_speed() {
+    let mut planet = Planet::new(400.0, Vec2F::new(0.0, 0.0));
+    planet.motion = Motion::Path {
+        waypoints: vec![
+            Vec2F::new(0.0, 0.0),
+            Vec2F::new(30.0, 0.0),
+            Vec2F::new(30.0, 10.0),
+        ],
+        period: 100.0,
+    };
+    let planets = [planet];
+
+    // The loop is 30 + 10 + sqrt(1000) long
+    let length = 40.0 + 1000.0_f64.sqrt();
+    let pos = position_at(&planets, 0, 100.0 * 35.0 / length);
+
+    assert!((pos - Vec2F::new(30.0, 5.0)).magnitude() < EPSILON);
+    assert!((position_at(&planets, 0, 100.0) - Vec2F::ZERO).magnitude() < EPSILON);
+}
+
+#[test]
+fn sliding_wall_hits_a_still_probe() {
+    let mut wall = Wall::new(Vec2F::new(190.0, 100.0), Vec2F::new(190.0, 140.0));
+    wall.motion = WallMotion::Slide {
+        offset: Vec2F::new(40.0, 0.0),
+        speed: 20.0,
+        ping_pong: false,
+    };
+
+    // The wall sweeps across the probe halfway through the tick
+    let time = wall
+        .intersects(Vec2F::new(200.0, 120.0), Vec2F::new(200.0, 120.0), 0.0, 1.0)
+        .expect("wall slides into the probe");
+
+    assert!((time - 0.5).abs() < EPSILON);
+}
+
+#[test]
+fn probe_passes_behind_a_moving_wall() {
+    let mut wall = Wall::new(Vec2F::new(200.0, 100.0), Vec2F::new(200.0, 140.0));
+    wall.motion = WallMotion::Slide {
+        offset: Vec2F::new(0.0, -100.0),
+        speed: 100.0,
+        ping_pong: true,
+    };
+
+    // The probe crosses where the wall started, but the wall is gone by the time it gets there
+    assert!(wall
+        .intersects(Vec2F::new(150.0, 120.0), Vec2F::new(250.0, 120.0), 0.0, 1.0)
+        .is_none());
+    // A still wall would have been hit
+    assert!(Wall::new(wall.pos1, wall.pos2)
+        .intersects(Vec2F::new(150.0, 120.0), Vec2F::new(250.0, 120.0), 0.0, 1.0)
+        .is_some());
+}
+
+#[test]
+fn rotating_wall_sweeps_into_the_probe() {
+    let mut simulation = simulation(Vec2F::new(230.0, 120.0), Vec2F::ZERO);
+    let mut wall = Wall::new(Vec2F::new(200.0, 120.0), Vec2F::new(240.0, 120.0));
+    wall.motion = WallMotion::Rotate {
+        pivot: Vec2F::new(200.0, 120.0),
+        speed: 0.1,
+        sweep: std::f64::consts::PI,
+        ping_pong: true,
+    };
+    // Start the probe just ahead of the wall as it turns
+    simulation.player.pos = Vec2F::new(200.0, 120.0) + Vec2F::new(30.0, 0.0).rotated(0.25);
+    simulation.walls.push(wall);
+
+    let mut event = None;
+    for _ in 0..5 {
+        event = simulation.tick();
+        if event.is_some() {
+            break;
+        }
+    }
+    let event = event.expect("wall catches the probe");
+
+    assert_eq!(event.cause, Cause::Wall(0));
+    assert_eq!(event.tick, 3);
+}
+
+#[test]
+fn wormholes_send_the_probe_out_of_the_other_end() {
+    let mut simulation = simulation(Vec2F::new(95.0, 100.0), Vec2F::new(2.0, 0.0));
+    let mut wormhole = Wormhole::new([Vec2F::new(100.0, 100.0), Vec2F::new(300.0, 50.0)], 4.0);
+    wormhole.rotation = std::f64::consts::FRAC_PI_2;
+    simulation.wormholes.push(wormhole);
+
+    // Flies 1 pixel into the first end, comes out turned to fly down the screen
+    assert!(simulation.tick().is_none());
+    assert!((simulation.player.pos - Vec2F::new(300.0, 47.0)).magnitude() < EPSILON);
+    assert!((simulation.player.velocity - Vec2F::new(0.0, 2.0)).magnitude() < EPSILON);
+
+    // Doesn't go straight back in while still inside the second end
+    assert!(simulation.tick().is_none());
+    assert!((simulation.player.pos - Vec2F::new(300.0, 49.0)).magnitude() < EPSILON);
+}
+
+#[test]
+fn target_only_counts_after_every_checkpoint() {
+    let mut simulation = simulation(Vec2F::new(100.0, 100.0), Vec2F::new(5.0, 0.0));
+    simulation.target.pos = Vec2F::new(120.0, 100.0);
+    simulation.target.size = 5.0;
+    simulation.checkpoints = vec![
+        Checkpoint::new(Vec2F::new(160.0, 100.0), 5.0),
+        Checkpoint::new(Vec2F::new(140.0, 100.0), 5.0),
+    ];
+    simulation.checkpoints_passed = vec![None; 2];
+    simulation.checkpoints_ordered = true;
+
+    let mut event = None;
+    for _ in 0..40 {
+        event = simulation.tick();
+        if event.is_some() {
+            break;
+        }
+    }
+
+    // Flies through the target, then passes the second checkpoint before the first, which
+    // doesn't count when they're ordered
+    assert!(event.is_none());
+    assert_eq!(simulation.checkpoints_passed, vec![Some(12), None]);
+    assert!(!simulation.is_target_open());
+}
+
+#[test]
+fn stars_add_to_the_score() {
+    let mut simulation = simulation(Vec2F::new(100.0, 100.0), Vec2F::new(5.0, 0.0));
+    simulation.target.pos = Vec2F::new(150.0, 100.0);
+    simulation.target.size = 5.0;
+    simulation.stars = vec![
+        Star::new(Vec2F::new(120.0, 102.0)),
+        Star::new(Vec2F::new(120.0, 140.0)),
+    ];
+    simulation.stars_collected = vec![None; 2];
+
+    let mut event = None;
+    for _ in 0..20 {
+        event = simulation.tick();
+        if event.is_some() {
+            break;
+        }
+    }
+    let event = event.expect("probe reaches the target");
+
+    // One star, plus a tenth of a point for each of the 2990 ticks left
+    assert_eq!(event.cause, Cause::Target);
+    assert_eq!(event.stars, 1);
+    assert_eq!(event.score, 100 + 299);
+}
+
+#[test]
+fn wind_only_pushes_the_probe_inside_its_zone() {
+    let zone = Zone::rectangle(
+        Vec2F::new(50.0, 50.0),
+        Vec2F::new(100.0, 100.0),
+        ZoneEffect::Wind(Vec2F::new(0.1, 0.0)),
+    );
+
+    let mut inside = simulation(Vec2F::new(100.0, 100.0), Vec2F::ZERO);
+    inside.zones = vec![zone.clone()];
+
+    for _ in 0..5 {
+        assert!(inside.tick().is_none());
+    }
+    assert!((inside.player.velocity.x - 0.5).abs() < 1e-6);
+
+    let mut outside = simulation(Vec2F::new(300.0, 100.0), Vec2F::new(0.0, 1.0));
+    outside.zones = vec![zone];
+
+    for _ in 0..5 {
+        assert!(outside.tick().is_none());
+    }
+    assert_eq!(outside.player.velocity, Vec2F::new(0.0, 1.0));
+}
+
+#[test]
+fn bouncy_walls_reflect_from_the_point_of_impact() {
+    let mut wall = Wall::new(Vec2F::new(200.0, 100.0), Vec2F::new(200.0, 140.0));
+    wall.kind = WallKind::Bouncy { restitution: 0.5 };
+
+    let mut simulation = simulation(Vec2F::new(145.0, 120.0), Vec2F::new(10.0, 0.0));
+    simulation.walls.push(wall);
+
+    for _ in 0..6 {
+        assert!(simulation.tick().is_none());
+    }
+
+    // Hits the wall halfway through the sixth tick, then flies back half as fast for the rest
+    assert!((simulation.player.pos.x - 197.5).abs() < 1e-5);
+    assert!((simulation.player.velocity.x + 5.0).abs() < EPSILON);
+}
+
+#[test]
+fn one_way_walls_only_let_the_probe_through_one_way() {
+    let mut wall = Wall::new(Vec2F::new(200.0, 100.0), Vec2F::new(200.0, 140.0));
+    wall.kind = WallKind::OneWay;
+    assert_eq!(wall.normal(), Vec2F::new(-1.0, 0.0));
+
+    let mut through = simulation(Vec2F::new(255.0, 120.0), Vec2F::new(-10.0, 0.0));
+    through.walls.push(wall.clone());
+
+    for _ in 0..10 {
+        assert!(through.tick().is_none());
+    }
+
+    let mut blocked = simulation(Vec2F::new(145.0, 120.0), Vec2F::new(10.0, 0.0));
+    blocked.walls.push(wall);
+
+    let event = (0..10).find_map(|_| blocked.tick());
+    assert_eq!(event.map(|event| event.cause), Some(Cause::Wall(0)));
+}
+
+#[test]
+fn burns_push_the_probe_until_the_fuel_runs_out() {
+    let mut simulation = simulation(Vec2F::new(100.0, 100.0), Vec2F::new(1.0, 0.0));
+    simulation.fuel = 3.0;
+    simulation.burns = vec![Burn::new(2, 10, Vec2F::new(0.0, 2.0))];
+
+    for _ in 0..10 {
+        assert!(simulation.tick().is_none());
+    }
+
+    // Two ticks coasting, then burning for the three ticks there's fuel for
+    assert!(simulation.fuel.abs() < EPSILON);
+    assert!((simulation.player.velocity.y / THRUST - 3.0).abs() < 1e-6);
+    assert!(simulation.inputs.is_empty());
+}
+
+#[test]
+fn held_thrust_is_recorded() {
+    let mut simulation = simulation(Vec2F::new(100.0, 100.0), Vec2F::new(1.0, 0.0));
+    simulation.fuel = 100.0;
+
+    simulation.tick();
+    simulation.set_thrust(Vec2F::new(-1.0, 0.0));
+    simulation.set_thrust(Vec2F::new(-1.0, 0.0));
+    simulation.tick();
+    simulation.tick();
+    simulation.set_thrust(Vec2F::ZERO);
+    simulation.tick();
+
+    assert!((simulation.player.velocity.x - 2.0f64.mul_add(-THRUST, 1.0)).abs() < EPSILON);
+    assert_eq!(
+        simulation.inputs,
+        vec![
+            ThrustInput {
+                tick: 1,
+                direction: Vec2F::new(-1.0, 0.0)
+            },
+            ThrustInput {
+                tick: 3,
+                direction: Vec2F::ZERO
+            },
+        ]
+    );
+}
+
+#[test]
+fn the_field_is_the_slope_of_the_potential() {
+    let mut simulation = simulation(Vec2F::ZERO, Vec2F::ZERO);
+    simulation.planets = vec![
+        Planet::new(400.0, Vec2F::new(200.0, 120.0)),
+        Planet::new(-250.0, Vec2F::new(120.0, 60.0)),
+    ];
+
+    let pos = Vec2F::new(150.0, 140.0);
+    let field = simulation
+        .pulls_at(pos)
+        .fold(Vec2F::ZERO, |total, pull| total + pull);
+
+    // Gravity pulls downhill, towards lower potential
+    let step = 1e-4;
+    let slope = Vec2F::new(
+        simulation.potential_at(pos + Vec2F::new(step, 0.0))
+            - simulation.potential_at(pos - Vec2F::new(step, 0.0)),
+        simulation.potential_at(pos + Vec2F::new(0.0, step))
+            - simulation.potential_at(pos - Vec2F::new(0.0, step)),
+    ) * (0.5 / step);
+
+    assert!((field + slope).magnitude() < 1e-6);
+    assert!((simulation.acceleration_at(pos) - field).magnitude() < EPSILON);
+}
+
+#[test]
+fn physics_profiles_round_trip_through_metadata() {
+    let custom = PhysicsProfile {
+        gravity: 0.4,
+        max_power: 4.0,
+        integrator: Integrator::RungeKutta4,
+        parity: false,
+        ..PhysicsProfile::GAME
+    };
+
+    for profile in [PhysicsProfile::GAME, custom] {
+        let line = profile.to_string();
+        let parsed = PhysicsProfile::from_words(line.split_whitespace()).expect("it parses");
+
+        assert_eq!(parsed, profile, "`{line}` didn't round trip");
+    }
+
+    assert_eq!(PhysicsProfile::GAME.to_string(), "game");
+    assert!(custom.to_string().starts_with("custom 0.4 12 1 4"));
+}
+
+#[test]
+fn physics_profiles_reject_nonsense() {
+    let parse = |line: &str| PhysicsProfile::from_words(line.split_whitespace());
+
+    assert!(parse("no-such-profile").is_err());
+    assert!(parse("custom 0.55 12 3 1 euler").is_err());
+    assert!(parse("custom 0.55 12 1 3 leapfrog").is_err());
+    assert!(parse("custom 0.55 12").is_err());
+
+    // Every constant has to be a usable number
+    for line in [
+        "custom NaN 12 1 3 euler",
+        "custom inf 12 1 3 euler",
+        "custom 0 12 1 3 euler",
+        "custom -0.55 12 1 3 euler",
+        "custom 0.55 0 1 3 euler",
+        "custom 0.55 12 -1 3 euler",
+        "custom 0.55 12 1 inf euler",
+    ] {
+        assert!(parse(line).is_err(), "`{line}` was accepted");
+    }
+    assert_eq!(
+        parse("custom 0 12 1 3 euler"),
+        Err(String::from("Gravity must be above 0, not 0"))
+    );
+}
+
+#[test]
+fn cycling_physics_visits_every_profile_with_parity_only_for_the_game() {
+    let mut profile = PhysicsProfile::GAME;
+
+    let mut names = Vec::new();
+    for _ in 0..5 {
+        profile.cycle();
+        let name = profile.name().expect("every step is a built in profile");
+        assert_eq!(profile.parity, name == "game", "{name}");
+        names.push(name);
+    }
+
+    // Picking an integrator only matters outside parity mode
+    profile.cycle_integrator();
+    assert_eq!(profile.integrator, Integrator::VelocityVerlet);
+    assert!(!profile.parity);
+
+    assert_eq!(
+        names,
+        [
+            "low-gravity",
+            "high-gravity",
+            "strong-launch",
+            "small-crash",
+            "game"
+        ]
+    );
+}
+
+#[test]
+fn physics_profiles_clamp_power_and_size_crashes() {
+    let physics = PhysicsProfile::GAME;
+
+    assert!((physics.clamp_power(0.2) - 1.0).abs() < EPSILON);
+    assert!((physics.clamp_power(2.5) - 2.5).abs() < EPSILON);
+    assert!((physics.clamp_power(7.0) - 3.0).abs() < EPSILON);
+
+    // A planet of mass 120 crashes the probe 10 pixels from its centre
+    assert!((physics.crash_radius_squared(120.0) - 100.0).abs() < EPSILON);
+
+    let small = PhysicsProfile {
+        crash_radius_divisor: 24.0,
+        ..physics
+    };
+    assert!((small.crash_radius_squared(120.0) - 25.0).abs() < EPSILON);
+}
+
+/// The largest the probe's energy strays from its launch value, relative to it, over two loops
+/// of a circular orbit around a single planet
+fn orbit_energy_drift(integrator: Integrator) -> f64 {
+    const RADIUS: f64 = 60.0;
+
+    let centre = Vec2F::new(200.0, 120.0);
+    let mut simulation = simulation(centre - Vec2F::new(0.0, RADIUS), Vec2F::ZERO);
+    simulation.planets = vec![Planet::new(100.0, centre)];
+    simulation.physics.integrator = integrator;
+    simulation.limits.max_ticks = u32::MAX;
+
+    let speed = (simulation.physics.gravity * 100.0 / RADIUS).sqrt();
+    simulation.player.velocity = Vec2F::new(speed, 0.0);
+
+    let period = std::f64::consts::TAU * RADIUS / speed;
+    let launch = simulation.energy();
+    let mut drift: f64 = 0.0;
+
+    for _ in 0..(2.0 * period) as u32 {
+        assert!(simulation.tick().is_none(), "{integrator:?} left the orbit");
+        drift = drift.max(((simulation.energy() - launch) / launch).abs());
+    }
+
+    drift
+}
+
+#[test]
+fn integrators_keep_an_orbit_energy() {
+    let euler = orbit_energy_drift(Integrator::SemiImplicitEuler);
+    let verlet = orbit_energy_drift(Integrator::VelocityVerlet);
+    let rk4 = orbit_energy_drift(Integrator::RungeKutta4);
+
+    // Euler's error is bounded but large, Verlet's is far smaller and RK4's smaller again
+    assert!(euler < 1e-3, "euler drifted {euler:e}");
+    assert!(verlet < 1e-6, "verlet drifted {verlet:e}");
+    assert!(rk4 < 1e-8, "rk4 drifted {rk4:e}");
+}
+
+#[test]
+fn default_integrator_steps_like_the_game() {
+    let launch = |parity| {
+        let mut simulation = simulation(Vec2F::new(200.0, 60.0), Vec2F::new(1.0, 0.0));
+        simulation.planets = vec![Planet::new(100.0, Vec2F::new(200.0, 120.0))];
+        simulation.physics.parity = parity;
+        simulation
+    };
+
+    assert_eq!(
+        PhysicsProfile::GAME.integrator,
+        Integrator::SemiImplicitEuler
+    );
+
+    // The pull goes into the velocity first, then the new velocity moves the probe
+    let mut editor = launch(false);
+    let velocity = Vec2F::new(1.0, editor.physics.gravity * 100.0 / 3600.0);
+    editor.tick();
+
+    assert!((editor.player.velocity - velocity).magnitude() < EPSILON);
+    assert!((editor.player.pos - (Vec2F::new(200.0, 60.0) + velocity)).magnitude() < EPSILON);
+
+    // And the editor's physics flies exactly as parity mode does
+    let mut parity = launch(true);
+    parity.tick();
+
+    for _ in 0..200 {
+        assert_eq!(editor.player.pos, parity.player.pos);
+        assert_eq!(editor.player.velocity, parity.player.velocity);
+        editor.tick();
+        parity.tick();
+    }
+}
+
+#[test]
+fn motions_need_a_positive_period() {
+    let parse = |nums: &[f64]| {
+        let mut nums = nums.iter().copied();
+        Motion::from_nums(&mut || nums.next().ok_or_else(|| String::from("Missing number")))
+    };
+
+    let (planet, motion) = parse(&[0.0, 1.0, 120.0, 0.0, 40.0, 200.0, 120.0]).expect("it parses");
+    assert_eq!(planet, 0);
+    assert!(matches!(motion, Motion::Orbit { period, .. } if (period - 120.0).abs() < EPSILON));
+
+    assert!(parse(&[0.0, 1.0, 0.0, 0.0, 40.0, 200.0, 120.0]).is_err());
+    assert!(parse(&[0.0, 1.0, -120.0, 0.0, 40.0, 200.0, 120.0]).is_err());
+    assert!(parse(&[0.0, 3.0, f64::NAN, 1.0, 200.0, 120.0]).is_err());
+}
+
+#[test]
+fn only_plain_game_levels_default_to_parity() {
+    let parse = |metadata: &str| {
+        let text = format!("{}{metadata}", LevelData::default());
+        LevelData::parse(&text, "test").expect("the level parses")
+    };
+
+    assert!(parse("").physics.parity);
+    assert!(!parse("#!physics low-gravity\n").physics.parity);
+    assert!(!parse("#!physics custom 0.55 12 1 3 rk4\n").physics.parity);
+    assert!(!parse("#!substeps 4\n").physics.parity);
+    assert!(parse("#!substeps 4\n#!parity on\n").physics.parity);
+
+    // Turning parity off is saved with the level
+    let mut level_data = LevelData::default();
+    level_data.physics.parity = false;
+    let text = level_data.to_string();
+    assert!(text.contains("#!parity off"));
+    assert!(
+        !LevelData::parse(&text, "test")
+            .expect("the level parses")
+            .physics
+            .parity
+    );
+    assert!(!LevelData::default().to_string().contains("#!parity"));
+}
+
+#[test]
+fn picking_an_integrator_changes_the_flight() {
+    let mut level_data = LevelData::default();
+    level_data.player.velocity = Vec2F::new(2.0, -0.4);
+
+    let flight_end = |level_data: &LevelData| {
+        let mut simulation = Simulation::empty();
+        simulation.push(level_data);
+        for _ in 0..100 {
+            simulation.tick();
+        }
+        simulation.player.pos
+    };
+
+    // Cycling the integrator the way the editor does, starting from the game's parity physics
+    let mut ends = Vec::new();
+    for _ in Integrator::ALL {
+        level_data.physics.cycle_integrator();
+        assert!(!level_data.physics.parity);
+        ends.push((level_data.physics.integrator, flight_end(&level_data)));
+    }
+
+    for (i, (a, end_a)) in ends.iter().enumerate() {
+        for (b, end_b) in &ends[i + 1..] {
+            assert!(
+                (*end_a - *end_b).magnitude() > 1e-6,
+                "{a:?} and {b:?} flew the same path"
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use super::Vec2F;
+
+/// How much the probe's engine changes its velocity every tick it burns
+pub const THRUST: f64 = 0.05;
+
+/// A planned firing of the probe's engine
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Burn {
+    /// How many ticks into the flight the burn starts
+    pub tick: u32,
+    /// How many ticks the burn lasts
+    pub duration: u32,
+    /// Which way the probe is pushed, as a unit vector
+    pub direction: Vec2F,
+}
+
+impl Burn {
+    pub fn new(tick: u32, duration: u32, direction: Vec2F) -> Self {
+        Self {
+            tick,
+            duration,
+            direction: direction.normalised(),
+        }
+    }
+
+    /// Whether the engine is burning `elapsed` ticks into the flight
+    pub const fn is_burning(&self, elapsed: u32) -> bool {
+        elapsed >= self.tick && elapsed - self.tick < self.duration
+    }
+}
+
+/// A change to the thrust the player held during a flight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThrustInput {
+    /// The tick the input changed on
+    pub tick: u32,
+    /// The direction held from then on, or zero once every key is let go
+    pub direction: Vec2F,
+}
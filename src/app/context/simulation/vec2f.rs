@@ -35,6 +35,31 @@ impl Vec2F {
         self.x.hypot(self.y)
     }
 
+    pub fn dot(self, other: Self) -> f64 {
+        self.x.mul_add(other.x, self.y * other.y)
+    }
+
+    /// The z component of the cross product of the two vectors
+    pub fn cross(self, other: Self) -> f64 {
+        self.x.mul_add(other.y, -self.y * other.x)
+    }
+
+    /// Rotate the vector clockwise on screen by `angle` radians
+    pub fn rotated(self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+
+        Self::new(
+            self.x.mul_add(cos, -self.y * sin),
+            self.x.mul_add(sin, self.y * cos),
+        )
+    }
+
+    /// Bounce the vector off a surface with the unit `normal`, keeping `restitution` of the part
+    /// going into the surface
+    pub fn reflected(self, normal: Self, restitution: f64) -> Self {
+        self - normal * ((1.0 + restitution) * self.dot(normal))
+    }
+
     pub fn normalised(&self) -> Self {
         let mut normalised = *self / self.magnitude();
 
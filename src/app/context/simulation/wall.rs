@@ -1,18 +1,81 @@
-use super::Vec2F;
+use super::{Vec2F, WallMotion};
 
 fn ccw(a: Vec2F, b: Vec2F, c: Vec2F) -> bool {
     (c.y - a.y) * (b.x - a.x) > (b.y - a.y) * (c.x - a.x)
 }
 
-#[derive(Debug, Clone)]
+/// What a wall does to the probe when it hits it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallKind {
+    /// Crash the probe, like every wall in the game
+    Deadly,
+    /// Bounce the probe off, keeping `restitution` of the speed it hit the wall with
+    Bouncy { restitution: f64 },
+    /// Let the probe through when it flies the way [`Wall::normal`] points, and crash it otherwise
+    OneWay,
+}
+
+impl WallKind {
+    /// Cycle between deadly, bouncy and one-way
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Deadly => Self::Bouncy { restitution: 0.8 },
+            Self::Bouncy { .. } => Self::OneWay,
+            Self::OneWay => Self::Deadly,
+        }
+    }
+
+    pub fn change_restitution(&mut self, change: f64) {
+        if let Self::Bouncy { restitution } = self {
+            *restitution = (*restitution + change).clamp(0.0, 1.0);
+        }
+    }
+
+    /// Parse a wall kind from the level file. Returns the kind and the index of the wall it
+    /// belongs to
+    pub fn from_nums(
+        pop: &mut impl FnMut() -> Result<f64, String>,
+    ) -> Result<(usize, Self), String> {
+        let wall = pop()? as usize;
+
+        let kind = match pop()? as u32 {
+            1 => Self::Bouncy {
+                restitution: pop()?,
+            },
+            2 => Self::OneWay,
+            kind => return Err(format!("Unknown wall type {kind}")),
+        };
+
+        Ok((wall, kind))
+    }
+
+    /// Write a wall kind for the level file, in the order [`WallKind::from_nums`] reads it
+    pub fn to_nums(self, wall: usize) -> Option<String> {
+        match self {
+            Self::Deadly => None,
+            Self::Bouncy { restitution } => Some(format!("{wall} 1 {restitution}")),
+            Self::OneWay => Some(format!("{wall} 2")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Wall {
+    /// Where the wall starts, for moving walls
     pub pos1: Vec2F,
     pub pos2: Vec2F,
+    pub motion: WallMotion,
+    pub kind: WallKind,
 }
 
 impl Wall {
     pub const fn new(pos1: Vec2F, pos2: Vec2F) -> Self {
-        Self { pos1, pos2 }
+        Self {
+            pos1,
+            pos2,
+            motion: WallMotion::Static,
+            kind: WallKind::Deadly,
+        }
     }
 
     pub const fn from_nums(nums: &[f64]) -> Self {
@@ -24,10 +87,130 @@ impl Wall {
         Self::new(Vec2F::new(nums[0], nums[1]), Vec2F::new(nums[2], nums[3]))
     }
 
-    pub fn intersects(&self, other: &Self) -> bool {
-        // return ccw(ax, ay, cx, cy, dx, dy) != ccw(bx, by, cx, cy, dx, dy) &&
-        // ccw(ax, ay, bx, by, cx, cy) != ccw(ax, ay, bx, by, dx, dy);
-        ccw(self.pos1, other.pos1, other.pos2) != ccw(self.pos2, other.pos1, other.pos2)
-            && ccw(self.pos1, self.pos2, other.pos1) != ccw(self.pos1, self.pos2, other.pos2)
+    /// The game's wall check, which doesn't count lines that only touch the end of a wall
+    pub fn crosses(&self, a: Vec2F, b: Vec2F) -> bool {
+        ccw(self.pos1, a, b) != ccw(self.pos2, a, b)
+            && ccw(self.pos1, self.pos2, a) != ccw(self.pos1, self.pos2, b)
+    }
+
+    /// Where the wall is after `time` ticks
+    pub fn at(&self, time: f64) -> Self {
+        Self {
+            pos1: self.motion.transform(self.pos1, time),
+            pos2: self.motion.transform(self.pos2, time),
+            motion: self.motion,
+            kind: self.kind,
+        }
+    }
+
+    /// The unit vector at right angles to the wall, pointing to the right looking from `pos1` to
+    /// `pos2`
+    pub fn normal(&self) -> Vec2F {
+        let along = self.pos2 - self.pos1;
+
+        Vec2F::new(-along.y, along.x).normalised()
+    }
+
+    /// Where the editor's handle for the wall's motion is: where a sliding wall slides to, or the
+    /// pivot of a rotating one
+    pub fn motion_handle(&self) -> Option<Vec2F> {
+        match self.motion {
+            WallMotion::Static => None,
+            WallMotion::Slide { offset, .. } => Some((self.pos1 + self.pos2) / 2.0 + offset),
+            WallMotion::Rotate { pivot, .. } => Some(pivot),
+        }
+    }
+
+    /// Returns the fraction of the way along the path from `from` to `to` at which it first
+    /// touches the wall, if it does at all. The probe takes `dt` ticks to fly the path, starting
+    /// `time` ticks into the flight, and moving walls move with it. Touching either end of the
+    /// wall counts
+    pub fn intersects(&self, from: Vec2F, to: Vec2F, time: f64, dt: f64) -> Option<f64> {
+        if self.motion == WallMotion::Static {
+            return segment_intersection(self.pos1, self.pos2, from, to);
+        }
+
+        let start = self.at(time);
+        let end = self.at(time + dt);
+
+        if self.motion.jumps(time, dt) {
+            return segment_intersection(end.pos1, end.pos2, from, to);
+        }
+
+        // Both ends of the wall are treated as moving in a straight line during the step, which
+        // is close enough for rotating walls as long as they don't turn too far in one tick
+        let wall = start.pos2 - start.pos1;
+        let wall_change = (end.pos2 - end.pos1) - wall;
+        let offset = from - start.pos1;
+        let offset_change = (to - from) - (end.pos1 - start.pos1);
+
+        // The probe is on the wall's line when this cross product is 0
+        let a = wall_change.cross(offset_change);
+        let b = wall.cross(offset_change) + wall_change.cross(offset);
+        let c = wall.cross(offset);
+
+        let Some(mut times) = solve_quadratic(a, b, c) else {
+            // Always on the wall's line, so check where the wall starts
+            return segment_intersection(start.pos1, start.pos2, from, to);
+        };
+        times.sort_by(f64::total_cmp);
+
+        times.into_iter().find(|t| {
+            let wall = wall + wall_change * *t;
+            let offset = offset + offset_change * *t;
+            let along_wall = offset.dot(wall) / wall.dot(wall);
+
+            (0.0..=1.0).contains(t) && (0.0..=1.0).contains(&along_wall)
+        })
+    }
+}
+
+/// The fraction of the way from `from` to `to` at which the path first touches the still wall
+/// from `pos1` to `pos2`
+fn segment_intersection(pos1: Vec2F, pos2: Vec2F, from: Vec2F, to: Vec2F) -> Option<f64> {
+    let path = to - from;
+    let wall = pos2 - pos1;
+    let offset = pos1 - from;
+
+    let denominator = path.cross(wall);
+    let path_length_squared = path.dot(path);
+
+    if denominator == 0.0 {
+        // Parallel lines can only touch if they're on the same line
+        if offset.cross(path) != 0.0 || path_length_squared == 0.0 {
+            return None;
+        }
+
+        let start = offset.dot(path) / path_length_squared;
+        let end = start + wall.dot(path) / path_length_squared;
+        let (first, last) = (start.min(end), start.max(end));
+
+        return (first <= 1.0 && last >= 0.0).then_some(first.max(0.0));
     }
+
+    let time = offset.cross(wall) / denominator;
+    let along_wall = offset.cross(path) / denominator;
+
+    ((0.0..=1.0).contains(&time) && (0.0..=1.0).contains(&along_wall)).then_some(time)
+}
+
+/// The real roots of `a t^2 + b t + c`, or `None` if every `t` is a root
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<Vec<f64>> {
+    const EPSILON: f64 = 1e-12;
+
+    if a.abs() < EPSILON {
+        if b.abs() < EPSILON {
+            return (c.abs() >= EPSILON).then(Vec::new);
+        }
+
+        return Some(vec![-c / b]);
+    }
+
+    let discriminant = b.mul_add(b, -4.0 * a * c);
+    if discriminant < 0.0 {
+        return Some(Vec::new());
+    }
+
+    let root = discriminant.sqrt();
+    Some(vec![(-b - root) / (2.0 * a), (-b + root) / (2.0 * a)])
 }
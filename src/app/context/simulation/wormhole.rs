@@ -0,0 +1,62 @@
+use super::Vec2F;
+
+/// A pair of linked discs. Flying into either end sends the probe out of the other
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wormhole {
+    pub ends: [Vec2F; 2],
+    pub radius: f64,
+    /// How far the probe's velocity is turned, in radians, going from the first end to the
+    /// second. Going the other way turns it back
+    pub rotation: f64,
+}
+
+impl Wormhole {
+    pub const fn new(ends: [Vec2F; 2], radius: f64) -> Self {
+        Self {
+            ends,
+            radius,
+            rotation: 0.0,
+        }
+    }
+
+    pub const fn from_nums(nums: &[f64]) -> Self {
+        assert!(
+            nums.len() == 6,
+            "Wrong number of items passed to `Wormhole::from_nums`"
+        );
+
+        Self {
+            ends: [Vec2F::new(nums[0], nums[1]), Vec2F::new(nums[2], nums[3])],
+            radius: nums[4],
+            rotation: nums[5],
+        }
+    }
+
+    /// Which end `pos` is inside, if either
+    pub fn end_at(&self, pos: Vec2F) -> Option<usize> {
+        self.ends
+            .iter()
+            .position(|end| (*end - pos).magnitude() < self.radius)
+    }
+
+    /// Send a probe that flew into `end` out of the other one, keeping its offset from the centre
+    /// and turning both by the wormhole's rotation. Returns the new position and velocity
+    pub fn teleport(&self, end: usize, pos: Vec2F, velocity: Vec2F) -> (Vec2F, Vec2F) {
+        let rotation = if end == 0 {
+            self.rotation
+        } else {
+            -self.rotation
+        };
+        let exit = self.ends[1 - end];
+
+        (
+            exit + (pos - self.ends[end]).rotated(rotation),
+            velocity.rotated(rotation),
+        )
+    }
+
+    pub fn change_size(&mut self, change: f64) {
+        self.radius *= 1.0 + change;
+        self.radius = self.radius.max(4.0);
+    }
+}
@@ -0,0 +1,122 @@
+use std::f64::consts::PI;
+
+use super::Vec2F;
+
+/// What a zone does to the probe while it's inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoneEffect {
+    /// Push the probe with a constant acceleration, like a current
+    Wind(Vec2F),
+    /// Take away this fraction of the probe's velocity every tick
+    Drag(f64),
+    /// Multiply the pull of every planet
+    Gravity(f64),
+}
+
+impl ZoneEffect {
+    /// Cycle between wind, drag and gravity scaling
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Wind(_) => Self::Drag(0.02),
+            Self::Drag(_) => Self::Gravity(2.0),
+            Self::Gravity(_) => Self::Wind(Vec2F::new(0.02, 0.0)),
+        }
+    }
+
+    /// Make the effect stronger by `steps` notches
+    pub fn change_strength(&mut self, steps: f64) {
+        match self {
+            Self::Wind(acceleration) => {
+                let strength = steps.mul_add(0.005, acceleration.magnitude()).max(0.005);
+                *acceleration = acceleration.normalised() * strength;
+            }
+            Self::Drag(drag) => *drag = steps.mul_add(0.005, *drag).clamp(0.0, 1.0),
+            Self::Gravity(scale) => *scale = steps.mul_add(0.25, *scale),
+        }
+    }
+
+    /// Turn the wind by `steps` notches of 15 degrees
+    pub fn rotate(&mut self, steps: f64) {
+        if let Self::Wind(acceleration) = self {
+            *acceleration = acceleration.rotated(steps * PI / 12.0);
+        }
+    }
+}
+
+/// A polygonal region that changes how the probe flies inside it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    /// The corners of the zone, in order around its edge
+    pub corners: Vec<Vec2F>,
+    pub effect: ZoneEffect,
+}
+
+impl Zone {
+    /// A rectangle with its top left corner at `pos`
+    pub fn rectangle(pos: Vec2F, size: Vec2F, effect: ZoneEffect) -> Self {
+        Self {
+            corners: vec![
+                pos,
+                pos + Vec2F::new(size.x, 0.0),
+                pos + size,
+                pos + Vec2F::new(0.0, size.y),
+            ],
+            effect,
+        }
+    }
+
+    /// The average of the corners, where the zone's handle is
+    pub fn centre(&self) -> Vec2F {
+        self.corners
+            .iter()
+            .fold(Vec2F::ZERO, |total, corner| total + *corner)
+            / self.corners.len().max(1) as f64
+    }
+
+    /// Whether `pos` is inside the zone, counting how many edges a ray to the right crosses
+    pub fn contains(&self, pos: Vec2F) -> bool {
+        let edges = self.corners.iter().zip(self.corners.iter().cycle().skip(1));
+
+        edges
+            .filter(|(a, b)| {
+                (a.y > pos.y) != (b.y > pos.y)
+                    && pos.x < (b.x - a.x) * (pos.y - a.y) / (b.y - a.y) + a.x
+            })
+            .count()
+            % 2
+            == 1
+    }
+
+    /// Parse a zone from the level file
+    pub fn from_nums(pop: &mut impl FnMut() -> Result<f64, String>) -> Result<Self, String> {
+        let effect = match pop()? as u32 {
+            1 => ZoneEffect::Wind(Vec2F::new(pop()?, pop()?)),
+            2 => ZoneEffect::Drag(pop()?),
+            3 => ZoneEffect::Gravity(pop()?),
+            kind => return Err(format!("Unknown zone type {kind}")),
+        };
+
+        let mut corners = Vec::new();
+        for _ in 0..pop()? as usize {
+            corners.push(Vec2F::new(pop()?, pop()?));
+        }
+
+        if corners.len() < 3 {
+            return Err(String::from("Zone has fewer than 3 corners"));
+        }
+
+        Ok(Self { corners, effect })
+    }
+
+    /// Write a zone for the level file, in the order [`Zone::from_nums`] reads it
+    pub fn to_nums(&self) -> String {
+        let effect = match self.effect {
+            ZoneEffect::Wind(acceleration) => format!("1 {acceleration}"),
+            ZoneEffect::Drag(drag) => format!("2 {drag}"),
+            ZoneEffect::Gravity(scale) => format!("3 {scale}"),
+        };
+        let corners: Vec<String> = self.corners.iter().map(ToString::to_string).collect();
+
+        format!("{effect} {} {}", corners.len(), corners.join(" "))
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use super::{analysis, lint, replay, LevelData};
+
+#[cfg(test)]
+mod tests;
+
+/// What a worker found out about the level
+pub enum Finding {
+    Stars(analysis::StarSearch),
+    Sensitivity(analysis::Sensitivity),
+    Lint(Vec<lint::Diagnostic>),
+}
+
+/// A slow check of the level, flown on its own thread so the window keeps drawing
+pub struct Task {
+    /// What the worker is doing, for the status line
+    pub name: &'static str,
+    /// The [`replay::level_hash`] of the level the worker was given
+    pub level_hash: u64,
+    receiver: Receiver<Finding>,
+}
+
+impl Task {
+    /// Start `work` on a copy of the level
+    pub fn spawn(
+        name: &'static str,
+        level_data: &LevelData,
+        work: impl FnOnce(&LevelData) -> Finding + Send + 'static,
+    ) -> Self {
+        let level_hash = replay::level_hash(level_data);
+        let level_data = level_data.clone();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            // The editor may have stopped waiting, so nobody's left to tell
+            let _ = sender.send(work(&level_data));
+        });
+
+        Self {
+            name,
+            level_hash,
+            receiver,
+        }
+    }
+
+    /// What the worker found, once it's done
+    pub fn poll(&self) -> Option<Result<Finding, String>> {
+        match self.receiver.try_recv() {
+            Ok(finding) => Some(Ok(finding)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(format!("{} stopped without finishing", self.name)))
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+use super::{analysis, replay, Finding, LevelData, Task};
+
+#[test]
+fn a_worker_finds_the_same_stars_as_searching_directly() {
+    let level_data = LevelData::default();
+    let task = Task::spawn("Searching for stars", &level_data, |level_data| {
+        Finding::Stars(analysis::collect_every_star(level_data))
+    });
+
+    assert_eq!(task.level_hash, replay::level_hash(&level_data));
+
+    let started = Instant::now();
+    let finding = loop {
+        if let Some(finding) = task.poll() {
+            break finding.expect("the worker finishes");
+        }
+
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "the worker hangs"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    let Finding::Stars(search) = finding else {
+        panic!("the worker searched for stars");
+    };
+    let direct = analysis::collect_every_star(&level_data);
+    assert_eq!(search.launch, direct.launch);
+    assert_eq!(search.collected, direct.collected);
+}
@@ -0,0 +1,111 @@
+//! Stepping a flight backwards and forwards, to see exactly how the probe got where it is
+
+use super::{
+    replay::{self, SNAPSHOT_SPACING},
+    ReplayInput, Simulation, SimulationEvent, Vec2F,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Where the timeline bar is drawn along the bottom of the screen
+pub const BAR_LEFT: i16 = 100;
+pub const BAR_WIDTH: i16 = 200;
+pub const BAR_Y: i16 = 222;
+
+/// The ticks flown so far in the current flight
+pub struct Timeline {
+    /// The flight at least every [`SNAPSHOT_SPACING`] ticks, starting from launch
+    snapshots: Vec<Simulation>,
+    /// The flight as far as it's got, while an earlier tick is being looked at
+    pub furthest: Option<Simulation>,
+    /// How the flight ended, if it has
+    pub end: Option<SimulationEvent>,
+}
+
+impl Timeline {
+    pub const fn new() -> Self {
+        Self {
+            snapshots: Vec::new(),
+            furthest: None,
+            end: None,
+        }
+    }
+
+    /// Start again from a flight that's just been launched
+    pub fn launch(&mut self, simulation: &Simulation) {
+        self.snapshots = vec![simulation.clone()];
+        self.furthest = None;
+        self.end = None;
+    }
+
+    /// Keep a snapshot of the flight, if it's gone far enough since the last one
+    pub fn record(&mut self, simulation: &Simulation) {
+        let due = self
+            .snapshots
+            .last()
+            .map_or(0, |snapshot| snapshot.ticks + SNAPSHOT_SPACING);
+
+        if simulation.ticks >= due {
+            self.snapshots.push(simulation.clone());
+        }
+    }
+
+    /// The flight as it was on `tick`, flown again from the nearest snapshot before it with the
+    /// thrust the player held in `furthest`
+    pub fn at(&self, tick: u32, furthest: &Simulation) -> Simulation {
+        let mut simulation = self
+            .snapshots
+            .iter()
+            .rev()
+            .find(|snapshot| snapshot.ticks <= tick)
+            .unwrap_or(furthest)
+            .clone();
+
+        let (speed, playing) = (simulation.speed, simulation.playing);
+        simulation.speed = 1;
+        simulation.playing = true;
+
+        let inputs: Vec<_> = furthest
+            .inputs
+            .iter()
+            .map(|input| (input.tick, ReplayInput::Thrust(input.direction)))
+            .collect();
+        let mut cursor = inputs.partition_point(|(at, _)| *at < simulation.ticks);
+
+        while simulation.ticks < tick {
+            replay::apply_inputs(&inputs, &mut cursor, &mut simulation);
+
+            if simulation.tick().is_some() {
+                break;
+            }
+        }
+
+        simulation.speed = speed;
+        simulation.playing = playing;
+
+        simulation
+    }
+
+    /// The furthest tick the flight has got to, given the tick being looked at
+    pub fn length(&self, simulation: &Simulation) -> u32 {
+        self.furthest
+            .as_ref()
+            .map_or(simulation.ticks, |furthest| furthest.ticks)
+    }
+
+    /// Forget everything after `tick`, so the flight can carry on differently from there
+    pub fn truncate(&mut self, tick: u32) {
+        self.snapshots.retain(|snapshot| snapshot.ticks <= tick);
+        self.furthest = None;
+        self.end = None;
+    }
+}
+
+/// The tick of a flight `length` ticks long under `pos` on the timeline bar, if it's on the bar
+pub fn tick_at(pos: Vec2F, length: u32) -> Option<u32> {
+    let along = (pos.x - f64::from(BAR_LEFT)) / f64::from(BAR_WIDTH);
+
+    ((0.0..=1.0).contains(&along) && (pos.y - f64::from(BAR_Y)).abs() <= 4.0)
+        .then(|| (along * f64::from(length)).round() as u32)
+}
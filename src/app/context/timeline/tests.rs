@@ -0,0 +1,59 @@
+use super::{tick_at, Simulation, Timeline, Vec2F, BAR_LEFT, BAR_WIDTH, BAR_Y};
+use crate::app::context::LevelData;
+
+/// A flight through an empty level at the given speed, which thrusts upwards for a while
+fn flight(speed: u32) -> (Timeline, Simulation) {
+    let mut level_data = LevelData::default();
+    level_data.planets.clear();
+    level_data.fuel = 30.0;
+    level_data.player.velocity = Vec2F::new(1.0, 0.5);
+
+    let mut simulation = Simulation::empty();
+    simulation.push(&level_data);
+    simulation.speed = speed;
+
+    let mut timeline = Timeline::new();
+    timeline.launch(&simulation);
+
+    while simulation.ticks < 130 {
+        match simulation.ticks {
+            12 => simulation.set_thrust(Vec2F::new(0.0, -1.0)),
+            40 => simulation.set_thrust(Vec2F::ZERO),
+            _ => (),
+        }
+
+        assert!(simulation.tick().is_none(), "the flight ends too early");
+        timeline.record(&simulation);
+    }
+
+    (timeline, simulation)
+}
+
+#[test]
+fn stepping_back_flies_the_same_way() {
+    let (timeline, furthest) = flight(4);
+    let (slow_timeline, slow) = flight(1);
+
+    let rewound = timeline.at(76, &furthest);
+    assert_eq!(rewound.ticks, 76);
+    assert_eq!(rewound.player.pos, slow_timeline.at(76, &slow).player.pos);
+    assert!(rewound.fuel < 30.0);
+
+    // Halfway through the thrust, which has to be held again on the way there
+    let thrusting = timeline.at(30, &furthest);
+    assert_eq!(thrusting.player.pos, slow_timeline.at(30, &slow).player.pos);
+    assert_eq!(thrusting.thrust(), Vec2F::new(0.0, -1.0));
+
+    let caught_up = timeline.at(furthest.ticks, &furthest);
+    assert_eq!(caught_up.player.pos, furthest.player.pos);
+    assert_eq!(caught_up.speed, 4);
+}
+
+#[test]
+fn clicking_the_bar_picks_a_tick() {
+    let middle = Vec2F::new(f64::from(BAR_LEFT + BAR_WIDTH / 2), f64::from(BAR_Y));
+
+    assert_eq!(tick_at(middle, 300), Some(150));
+    assert_eq!(tick_at(middle + Vec2F::new(0.0, 20.0), 300), None);
+    assert_eq!(tick_at(Vec2F::new(0.0, f64::from(BAR_Y)), 300), None);
+}
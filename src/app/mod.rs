@@ -1,6 +1,7 @@
 use sdl2::event::Event;
 use sdl2::keyboard::{Keycode, Mod};
 
+pub mod cli;
 mod context;
 mod renderer;
 mod tick;
@@ -1,17 +1,29 @@
 use sdl2::{gfx::primitives::DrawRenderer, pixels::Color, render::WindowCanvas, video::Window};
 
-use super::context::{AppState, Context, Simulation, Vec2F};
+use super::context::{
+    diff::{self, LevelDiff},
+    AppState, Context, FieldOverlay, History, Integrator, LevelData, Simulation, SimulationEvent,
+    SymmetryMode, Vec2F,
+};
 
 mod draw_objects;
+mod field;
 mod screenshot;
 
 pub const GRID_X_SIZE: u32 = 400;
 pub const GRID_Y_SIZE: u32 = 240;
 
+/// The most ticks of a flight predicted each frame, however long the level lets it last
+const PREVIEW_TICKS: u32 = 2000;
+
 pub struct Renderer {
     canvas: WindowCanvas,
     pixel_scale: u32,
     pub screenshot_next_frame: bool,
+    /// Counts up every frame, to animate previews of moving bodies
+    frame: u32,
+    /// The planets' gravity as it was last drawn, kept until they change
+    field: Option<field::Field>,
 }
 
 impl Renderer {
@@ -31,6 +43,8 @@ impl Renderer {
             canvas,
             pixel_scale,
             screenshot_next_frame: false,
+            frame: 0,
+            field: None,
         })
     }
 
@@ -55,32 +69,153 @@ impl Renderer {
     }
 
     pub fn draw(&mut self, context: &Context) -> Result<(), String> {
+        self.frame = self.frame.wrapping_add(1);
+
         let image_background_path = context
             .show_background_image
             .then(|| context.level_path.replace("obl", "png"));
 
         draw_objects::background(&mut self.canvas, context.state, image_background_path)?;
 
-        if matches!(context.state, AppState::Aiming) {
-            draw_objects::trajectory(&mut self.canvas, context, 2000, 1, Color::GREY)?;
-            draw_objects::trajectory(&mut self.canvas, context, 15, 4, Color::WHITE)?;
+        if let Some(history) = &context.history {
+            if let Some(level_data) = history.preview() {
+                return self.draw_preview(context, history, level_data);
+            }
         }
 
-        if matches!(context.state, AppState::Editing)
-            && context.level_data.player.velocity != Vec2F::ZERO
-        {
-            draw_objects::trajectory(&mut self.canvas, context, 2000, 1, Color::RGB(60, 60, 60))?;
+        if matches!(context.state, AppState::Editing) {
+            draw_objects::symmetry_guides(&self.canvas, context.level_data.symmetry)?;
         }
 
-        let drawn_level = if matches!(context.state, AppState::Flying | AppState::GameOver(_)) {
+        let mut drawn_level = if matches!(context.state, AppState::Flying | AppState::GameOver(_)) {
             context.simulation.clone()
         } else {
             let mut simulation = Simulation::empty();
             simulation.push(&context.level_data);
 
+            if matches!(context.state, AppState::Aiming) {
+                simulation.move_planets(f64::from(context.aim_time));
+            }
+
             simulation
         };
 
+        if context.field_overlay != FieldOverlay::Off {
+            self.draw_field(context.field_overlay, &drawn_level)?;
+        }
+
+        let max_ticks = context.level_data.limits.max_ticks.min(PREVIEW_TICKS);
+
+        if matches!(context.state, AppState::Aiming) {
+            draw_objects::trajectory(&mut self.canvas, context, max_ticks, 1, Color::GREY)?;
+            draw_objects::trajectory(&mut self.canvas, context, 15, 4, Color::WHITE)?;
+        }
+
+        if matches!(context.state, AppState::Editing)
+            && context.level_data.player.velocity != Vec2F::ZERO
+        {
+            draw_objects::trajectory(
+                &mut self.canvas,
+                context,
+                max_ticks,
+                1,
+                Color::RGB(60, 60, 60),
+            )?;
+        }
+
+        if matches!(context.state, AppState::Editing | AppState::Aiming) {
+            let show_handles = matches!(context.state, AppState::Editing)
+                && context.edit_selection.show_grab_indicators;
+
+            draw_objects::planet_motions(&self.canvas, &context.level_data.planets, show_handles)?;
+            draw_objects::wall_motions(
+                &self.canvas,
+                &context.level_data.walls,
+                f64::from(self.frame),
+                show_handles,
+            )?;
+        }
+
+        // Walls are drawn where they start while editing, so their ends can be grabbed
+        let wall_time = match context.state {
+            AppState::Editing => 0,
+            AppState::Aiming => context.aim_time,
+            AppState::Flying | AppState::GameOver(_) => drawn_level.ticks,
+        };
+        drawn_level.walls = drawn_level.walls_at(f64::from(wall_time));
+
+        // Diffed once a frame, for both the overlay and the bottom line
+        let comparison = comparison_diff(context);
+        self.draw_bodies(context, &drawn_level)?;
+        self.draw_comparison(comparison.as_ref())?;
+        self.draw_flight_markers(context, &drawn_level)?;
+
+        if context.show_energy_plot && matches!(context.state, AppState::Editing | AppState::Aiming)
+        {
+            draw_objects::energy_plot(&self.canvas, context)?;
+        }
+
+        if self.screenshot_next_frame {
+            self.screenshot_next_frame = false;
+            screenshot::screenshot(&self.canvas, self.pixel_scale, &context.level_path)?;
+        }
+
+        self.draw_hud_text(context, comparison.as_ref())?;
+
+        if let Some(history) = &context.history {
+            draw_objects::history(&self.canvas, history, &context.level_path)?;
+        }
+
+        self.canvas.present();
+
+        Ok(())
+    }
+
+    /// Draw a saved version of the level in place of the one being edited, with the history panel
+    /// it was picked from
+    fn draw_preview(
+        &mut self,
+        context: &Context,
+        history: &History,
+        level_data: &LevelData,
+    ) -> Result<(), String> {
+        let mut preview = Simulation::empty();
+        preview.push(level_data);
+        self.draw_bodies(context, &preview)?;
+
+        if let Some(version) = history.selected() {
+            self.draw_text(
+                2,
+                2,
+                &format!("Previewing {}", version.label()),
+                Color::WHITE,
+            )?;
+        }
+        draw_objects::history(&self.canvas, history, &context.level_path)?;
+
+        self.canvas.present();
+
+        Ok(())
+    }
+
+    /// Draw every body in the level, where they are as of `drawn_level`
+    fn draw_bodies(&self, context: &Context, drawn_level: &Simulation) -> Result<(), String> {
+        let show_handles = matches!(context.state, AppState::Editing)
+            && context.edit_selection.show_grab_indicators;
+
+        draw_objects::zones(&self.canvas, &drawn_level.zones, show_handles)?;
+        draw_objects::wormholes(&self.canvas, &drawn_level.wormholes)?;
+        draw_objects::checkpoints(
+            &self.canvas,
+            &drawn_level.checkpoints,
+            &drawn_level.checkpoints_passed,
+            drawn_level.checkpoints_ordered,
+        )?;
+        draw_objects::stars(
+            &self.canvas,
+            &drawn_level.stars,
+            &drawn_level.stars_collected,
+        )?;
         draw_objects::planets(&self.canvas, &drawn_level.planets)?;
         draw_objects::player(&self.canvas, &drawn_level.player)?;
         draw_objects::target(&self.canvas, &drawn_level.target)?;
@@ -89,48 +224,310 @@ impl Renderer {
             &drawn_level.walls,
             context.state,
             context.edit_selection.show_grab_indicators,
-        )?;
+        )
+    }
 
-        if self.screenshot_next_frame {
-            self.screenshot_next_frame = false;
-            screenshot::screenshot(&self.canvas, self.pixel_scale, &context.level_path)?;
+    /// Draw the planets' gravity, sampling it again only if they've changed since last frame
+    fn draw_field(
+        &mut self,
+        overlay: FieldOverlay,
+        drawn_level: &Simulation,
+    ) -> Result<(), String> {
+        let field = match self.field.take() {
+            Some(field) if field.is_for(drawn_level) => field,
+            _ => field::Field::new(drawn_level),
+        };
+
+        field.draw(&self.canvas, overlay)?;
+        self.field = Some(field);
+
+        Ok(())
+    }
+
+    /// Show what's changed since the version of the level being compared with, while editing
+    fn draw_comparison(&self, comparison: Option<&(&str, LevelDiff)>) -> Result<(), String> {
+        comparison.map_or(Ok(()), |(_, diff)| {
+            draw_objects::comparison(&self.canvas, diff)
+        })
+    }
+
+    /// Draw what's specific to the flight: pinned ghosts, the time scrubber and planned burns
+    /// while aiming, the probe's exhaust while flying and what it hit once it's over
+    fn draw_flight_markers(
+        &self,
+        context: &Context,
+        drawn_level: &Simulation,
+    ) -> Result<(), String> {
+        if let Some(flights) = context
+            .ghost_flights()
+            .filter(|_| matches!(context.state, AppState::Editing | AppState::Aiming))
+        {
+            draw_objects::ghosts(&self.canvas, context, flights)?;
         }
 
-        self.draw_hud_text(context)?;
+        match context.state {
+            AppState::Aiming => {
+                if drawn_level.has_moving_bodies() || drawn_level.fuel > 0.0 {
+                    draw_objects::time_scrubber(&self.canvas, context)?;
+                }
 
-        self.canvas.present();
+                if !context.level_data.burns.is_empty() {
+                    draw_objects::planned_burns(&self.canvas, context)?;
+                }
+
+                if let Some(solution) = context.level_data.solution {
+                    draw_objects::solution(&self.canvas, &context.level_data.player, solution)?;
+                }
+            }
+
+            AppState::Flying => draw_objects::exhaust(&self.canvas, drawn_level)?,
+            AppState::GameOver(event) => draw_objects::impact(&self.canvas, drawn_level, event)?,
+            AppState::Editing => (),
+        }
+
+        if let Some(playback) = &context.playback {
+            draw_objects::replay_scrubber(&self.canvas, playback)?;
+        } else if matches!(context.state, AppState::Flying | AppState::GameOver(_)) {
+            draw_objects::flight_timeline(&self.canvas, context)?;
+
+            if !context.simulation.playing || matches!(context.state, AppState::GameOver(_)) {
+                draw_objects::forces(&self.canvas, drawn_level)?;
+            }
+        }
 
         Ok(())
     }
 
-    fn draw_hud_text(&self, context: &Context) -> Result<(), String> {
+    /// List the problems with the level along the bottom of the screen, worst first, and circle
+    /// where they are
+    fn draw_diagnostics(&self, context: &Context) -> Result<(), String> {
+        const SHOWN: usize = 4;
+
+        let diagnostics = context.diagnostics();
+        let mut lines: Vec<(String, Color)> = diagnostics
+            .iter()
+            .take(SHOWN)
+            .map(|diagnostic| {
+                draw_objects::diagnostic_marker(&self.canvas, diagnostic)?;
+                Ok((
+                    format!("! {}", diagnostic.message),
+                    draw_objects::severity_colour(diagnostic.severity),
+                ))
+            })
+            .collect::<Result<_, String>>()?;
+
+        if diagnostics.len() > SHOWN {
+            lines.push((
+                format!("and {} more", diagnostics.len() - SHOWN),
+                Color::RGB(160, 160, 160),
+            ));
+        }
+
+        for (i, (line, colour)) in (0..).zip(lines.iter().rev()) {
+            self.draw_text(2, 220 - i * 10, line, *colour)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_hud_text(
+        &self,
+        context: &Context,
+        comparison: Option<&(&str, LevelDiff)>,
+    ) -> Result<(), String> {
         // Current app state
         self.draw_text(2, 2, &context.state.to_string(), Color::WHITE)?;
 
+        if context.playback.is_some() {
+            self.draw_text(300, 2, "Replay", Color::WHITE)?;
+        }
+
+        if context.level_data.physics.parity {
+            self.draw_text(350, 2, "Parity", Color::CYAN)?;
+        }
+
         // Helper text
         let helper_text = match (context.show_hints, context.state) {
-            (true, AppState::Editing) => String::from("Drag planets and walls with mouse\nChange size by scrolling while holding\nA to spawn a new planet\nW to spawn a wall\nX to delete a selected body"),
+            (true, AppState::Editing) if context.history.is_some() => String::from("Up and Down to pick a version\nSpace to preview it\nEnter to restore it as a new save\nC to compare with it\nX to delete it\nShift+X to delete every older one\nEscape to close"),
+            (true, AppState::Editing) => String::from("Drag planets and walls with mouse\nChange size by scrolling while holding\nA to spawn a new planet\nW to spawn a wall\nX to delete a selected body\nY to change symmetry\nG to change physics\nShift+G to change integrator\nO to make a planet or wall move, [ ] for its speed\nT to spawn a wormhole\nC to spawn a checkpoint\nK to spawn a star\nZ to spawn a wind zone, Shift+Z to change it\nU to make a wall bouncy or one-way\nShift+I to make a black hole or ghost planet\nV to watch the last replay"),
+            (false, AppState::Editing) => edit_status(context),
 
-            (false, AppState::Aiming) => format!("Launch Strength: {:.2}", context.level_data.player.velocity.magnitude()),
-            (true, AppState::Aiming) => format!("Launch Strength: {:.2}\nAim with mouse\n", context.level_data.player.velocity.magnitude()),
+            (false, AppState::Aiming) => format!("Launch Strength: {:.2}{}", context.level_data.player.velocity.magnitude(), solution_status(context)),
+            (true, AppState::Aiming) => format!("Launch Strength: {:.2}\nAim with mouse\nScroll to see moving planets later\nShift+arrows to plan a burn there\nEnter to pin this launch as a ghost\nM to mark the intended launch\nShift+M to check how sensitive it is{}", context.level_data.player.velocity.magnitude(), solution_status(context)),
 
+            (true, AppState::Flying) if context.playback.is_some() => String::from("Space to pause\nLeft and Right to play backwards or forwards\n, and . to step a tick\nScroll to scrub"),
+            (true, AppState::Flying) if !context.simulation.playing => format!("Speed x{}\n{}\n, and . to step a tick\nScroll or click the timeline to scrub\nSpace to carry on from here", context.simulation.speed, tick_status(context)),
             (_, AppState::Flying) => {
-                let paused_text = if context.simulation.playing { "" } else { "Paused" };
+                let paused_text = if context.simulation.playing { String::new() } else { tick_status(context) };
                 format!("Speed x{}\n{paused_text}", context.simulation.speed)
             }
-            (true, AppState::GameOver(_)) => String::from("Press Escape to edit\nPress R to restart"),
-
-            (_, _) => String::new(),
+            (false, AppState::GameOver(event)) => format!("Final speed {:.2}\n{}", event.velocity.magnitude(), score(context, event)),
+            (true, AppState::GameOver(event)) => format!("Final speed {:.2}\n{}\nPress Escape to edit\nPress R to restart", event.velocity.magnitude(), score(context, event)),
         };
 
         self.draw_text(2, 12, &helper_text, Color::YELLOW)?;
 
+        let simulation = &context.simulation;
+        if matches!(context.state, AppState::Flying) && !simulation.stars.is_empty() {
+            let stars = format!(
+                "Stars {}/{}",
+                simulation.stars_collected(),
+                simulation.stars.len()
+            );
+            self.draw_text(300, 22, &stars, Color::YELLOW)?;
+        }
+
+        if matches!(context.state, AppState::Flying | AppState::GameOver(_))
+            && !simulation.checkpoints.is_empty()
+        {
+            let progress = format!(
+                "Checkpoints {}/{}",
+                simulation.checkpoint_progress(),
+                simulation.checkpoints.len()
+            );
+            self.draw_text(300, 12, &progress, Color::RGB(255, 170, 60))?;
+        }
+
+        if matches!(context.state, AppState::Flying | AppState::GameOver(_))
+            && context.level_data.fuel > 0.0
+        {
+            let fuel = format!("Fuel {:.0}/{}", simulation.fuel, context.level_data.fuel);
+            self.draw_text(300, 32, &fuel, Color::RGB(255, 120, 40))?;
+        }
+
+        if let Some(task) = context.task_status() {
+            self.draw_text(300, 42, &format!("{task}..."), Color::CYAN)?;
+        }
+
+        if matches!(context.state, AppState::Editing) {
+            self.draw_diagnostics(context)?;
+        }
+
         // Current level path
         let display_path = context
             .level_path
             .split('/')
             .last()
             .unwrap_or(&context.level_path);
-        self.draw_text(2, 230, &format!("Editing {display_path}"), Color::WHITE)
+        let comparison = comparison.map_or_else(String::new, |(name, diff)| {
+            format!(
+                " vs {name} (+{} -{} >{})",
+                diff.planets.added.len() + diff.walls.added.len(),
+                diff.planets.removed.len() + diff.walls.removed.len(),
+                diff.moved_planets().count() + diff.moved_walls().count()
+            )
+        });
+        self.draw_text(
+            2,
+            230,
+            &format!("Editing {display_path}{comparison}"),
+            Color::WHITE,
+        )
+    }
+}
+
+/// The stars collected and score of a finished flight
+fn score(context: &Context, event: SimulationEvent) -> String {
+    format!(
+        "Stars {}/{}  Score {}",
+        event.stars,
+        context.simulation.stars.len(),
+        event.score
+    )
+}
+
+/// The tick being looked at in a paused flight, with how fast the probe is going and how hard it's
+/// being pushed
+fn tick_status(context: &Context) -> String {
+    let simulation = &context.simulation;
+    let pos = simulation.player.pos;
+
+    format!(
+        "Paused at tick {}\nVelocity {:.2}  Force {:.4}",
+        simulation.ticks,
+        simulation.player.velocity.magnitude(),
+        simulation.acceleration_at(pos).magnitude()
+    )
+}
+
+/// How far the intended launch can be changed before it ends differently, if that's been checked
+/// since the level last changed
+fn solution_status(context: &Context) -> String {
+    let Some(sensitivity) = context.sensitivity() else {
+        return String::new();
+    };
+
+    let margin = |margin: Option<f64>| {
+        margin.map_or_else(|| String::from("-"), |margin| format!("{margin:.2}"))
+    };
+    let mut lines = vec![
+        format!("Intended launch: {}", sensitivity.cause),
+        format!(
+            "Angle {} deg  Power {}",
+            margin(sensitivity.angle.tightest()),
+            margin(sensitivity.power.tightest())
+        ),
+    ];
+
+    if let Some((body, distance)) = sensitivity.tightest_move() {
+        lines.push(format!("Tightest: {body} moved {distance:.1} px"));
+    }
+
+    format!("\n{}", lines.join("\n"))
+}
+
+/// Describe any editor or level settings that aren't at their defaults
+fn edit_status(context: &Context) -> String {
+    let mut lines = Vec::new();
+
+    if context.level_data.symmetry.mode != SymmetryMode::Off {
+        lines.push(format!("Symmetry: {}", context.level_data.symmetry.mode));
+    }
+
+    let checkpoints = &context.level_data.checkpoints;
+    if !checkpoints.is_empty() {
+        let order = if context.level_data.checkpoints_ordered {
+            "in order"
+        } else {
+            "any order"
+        };
+        lines.push(format!("Checkpoints: {} ({order})", checkpoints.len()));
+    }
+
+    if context.level_data.fuel > 0.0 {
+        lines.push(format!("Fuel: {}", context.level_data.fuel));
+    }
+
+    let physics = context.level_data.physics;
+    if physics.name() != Some("game") {
+        let name = physics.name().unwrap_or("custom");
+        lines.push(format!("Physics: {name} ({})", physics.integrator.name()));
     }
+
+    // Parity mode flies like the game, so the level's own choices are ignored
+    let mut ignored = Vec::new();
+    if physics.integrator != Integrator::SemiImplicitEuler {
+        ignored.push(format!("{} integrator", physics.integrator.name()));
+    }
+    if context.level_data.substeps != 1 {
+        ignored.push(format!("{} substeps", context.level_data.substeps));
+    }
+    if physics.parity && !ignored.is_empty() {
+        lines.push(format!("Parity ignores the {}", ignored.join(" and ")));
+    }
+
+    lines.join("\n")
+}
+
+/// What's changed since the version of the level being compared with, and its name, while
+/// editing
+fn comparison_diff(context: &Context) -> Option<(&str, LevelDiff<'_>)> {
+    let comparison = context.comparison.as_ref()?;
+
+    matches!(context.state, AppState::Editing).then(|| {
+        (
+            comparison.name.as_str(),
+            diff::diff(&comparison.level_data, &context.level_data),
+        )
+    })
 }
@@ -3,9 +3,20 @@ use sdl2::{
     render::WindowCanvas,
 };
 use std::f64::consts::PI;
+use std::time::SystemTime;
 
 use super::{GRID_X_SIZE, GRID_Y_SIZE};
-use crate::app::context::{AppState, Context, Planet, Player, Simulation, Target, Wall};
+use crate::app::context::{
+    diff::LevelDiff,
+    ghost,
+    lint::{Diagnostic, Severity},
+    position_at,
+    timeline::{BAR_LEFT, BAR_WIDTH, BAR_Y},
+    Anchor, AppState, Checkpoint, Context, History, Integrator, LevelData, Motion, Planet,
+    PlanetKind, Playback, Player, ReplayInput, Simulation, SimulationCause, SimulationEvent, Star,
+    Symmetry, SymmetryMode, Target, Vec2F, Wall, WallKind, WallMotion, Wormhole, Zone, ZoneEffect,
+    THRUST,
+};
 
 pub fn background(
     canvas: &mut WindowCanvas,
@@ -43,13 +54,29 @@ pub fn background(
 pub fn planets(canvas: &WindowCanvas, planets: &[Planet]) -> Result<(), String> {
     for planet in planets {
         let pos = ((planet.pos.x.round()) as i16, (planet.pos.y.round()) as i16);
-        let radius = ((planet.mass.abs() / 12.0).round()) as i16;
+        let radius = (planet.radius().round()) as i16;
 
-        if planet.mass.is_sign_positive() {
-            canvas.filled_circle(pos.0, pos.1, radius, Color::GREY)?;
-        } else {
-            canvas.circle(pos.0, pos.1, radius, Color::GREY)?;
-        };
+        match planet.kind {
+            PlanetKind::Normal => canvas.filled_circle(pos.0, pos.1, radius, Color::GREY)?,
+            PlanetKind::Anti => canvas.circle(pos.0, pos.1, radius, Color::GREY)?,
+
+            // Only the event horizon is solid, so the rest of the planet is just a faint glow
+            PlanetKind::BlackHole { horizon } => {
+                canvas.filled_circle(pos.0, pos.1, radius, Color::RGBA(255, 140, 40, 30))?;
+                canvas.filled_circle(pos.0, pos.1, horizon.round() as i16, Color::BLACK)?;
+                canvas.circle(
+                    pos.0,
+                    pos.1,
+                    horizon.round() as i16 + 1,
+                    Color::RGB(255, 140, 40),
+                )?;
+            }
+
+            PlanetKind::Ghost => {
+                canvas.filled_circle(pos.0, pos.1, radius, Color::RGBA(200, 200, 200, 50))?;
+                canvas.aa_circle(pos.0, pos.1, radius, Color::RGBA(200, 200, 200, 100))?;
+            }
+        }
     }
 
     Ok(())
@@ -88,15 +115,59 @@ pub fn walls(
     show_grab_indicators: bool,
 ) -> Result<(), String> {
     for wall in walls {
+        let colour = match wall.kind {
+            WallKind::Deadly => Color::RGB(200, 200, 200),
+            WallKind::Bouncy { .. } => Color::RGB(90, 200, 255),
+            WallKind::OneWay => Color::RGB(120, 220, 120),
+        };
+
         canvas.thick_line(
             wall.pos1.x.round() as i16,
             wall.pos1.y.round() as i16,
             wall.pos2.x.round() as i16,
             wall.pos2.y.round() as i16,
             2,
-            Color::RGB(200, 200, 200),
+            colour,
         )?;
 
+        match wall.kind {
+            WallKind::Deadly => (),
+
+            WallKind::Bouncy { restitution } => {
+                if matches!(state, AppState::Editing) && show_grab_indicators {
+                    let middle = (wall.pos1 + wall.pos2) / 2.0 + wall.normal() * 6.0;
+                    canvas.string(
+                        middle.x.round() as i16 - 12,
+                        middle.y.round() as i16 - 3,
+                        &format!("{restitution:.1}"),
+                        colour,
+                    )?;
+                }
+            }
+
+            // Chevrons along the wall, pointing the way the probe can pass through
+            WallKind::OneWay => {
+                let along = wall.pos2 - wall.pos1;
+                let normal = wall.normal() * 4.0;
+                let chevrons = (along.magnitude() / 12.0).floor().max(1.0) as u32;
+
+                for k in 0..chevrons {
+                    let centre = wall.pos1 + along * ((f64::from(k) + 0.5) / f64::from(chevrons));
+                    let side = along.normalised() * 3.0;
+
+                    for arm in [centre + side, centre - side] {
+                        canvas.line(
+                            arm.x.round() as i16,
+                            arm.y.round() as i16,
+                            (centre.x + normal.x).round() as i16,
+                            (centre.y + normal.y).round() as i16,
+                            colour,
+                        )?;
+                    }
+                }
+            }
+        }
+
         if matches!(state, AppState::Editing) && show_grab_indicators {
             canvas.circle(
                 wall.pos1.x.round() as i16,
@@ -116,11 +187,829 @@ pub fn walls(
     Ok(())
 }
 
+/// Draw the checkpoint rings. Passed checkpoints are green, and ordered ones are numbered
+pub fn checkpoints(
+    canvas: &WindowCanvas,
+    checkpoints: &[Checkpoint],
+    passed: &[Option<u32>],
+    ordered: bool,
+) -> Result<(), String> {
+    for (i, checkpoint) in checkpoints.iter().enumerate() {
+        let colour = if passed.get(i).copied().flatten().is_some() {
+            Color::GREEN
+        } else {
+            Color::RGB(255, 170, 60)
+        };
+        let x = checkpoint.pos.x.round() as i16;
+        let y = checkpoint.pos.y.round() as i16;
+        let radius = checkpoint.radius.round() as i16;
+
+        canvas.circle(x, y, radius, colour)?;
+        canvas.circle(x, y, radius - 2, colour)?;
+
+        if ordered {
+            canvas.string(x - 3, y - 3, &(i + 1).to_string(), colour)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the stars that haven't been collected yet
+pub fn stars(
+    canvas: &WindowCanvas,
+    stars: &[Star],
+    collected: &[Option<u32>],
+) -> Result<(), String> {
+    for (i, star) in stars.iter().enumerate() {
+        if collected.get(i).copied().flatten().is_some() {
+            continue;
+        }
+
+        let x = star.pos.x.round() as i16;
+        let y = star.pos.y.round() as i16;
+
+        canvas.filled_trigon(x, y - 5, x - 4, y + 3, x + 4, y + 3, Color::YELLOW)?;
+        canvas.filled_trigon(x, y + 5, x - 4, y - 3, x + 4, y - 3, Color::YELLOW)?;
+    }
+
+    Ok(())
+}
+
+/// The colour of the trajectory after `passed` checkpoints have been reached
+const fn checkpoint_colour(colour: Color, passed: usize) -> Color {
+    const COLOURS: [Color; 4] = [
+        Color::RGB(255, 170, 60),
+        Color::CYAN,
+        Color::MAGENTA,
+        Color::GREEN,
+    ];
+
+    match passed {
+        0 => colour,
+        n => COLOURS[(n - 1) % COLOURS.len()],
+    }
+}
+
+/// Shade the zones by what they do: blue for wind, with arrows showing which way it blows, brown
+/// for drag and purple for gravity
+pub fn zones(canvas: &WindowCanvas, zones: &[Zone], show_handles: bool) -> Result<(), String> {
+    const ARROW_SPACING: f64 = 20.0;
+
+    for zone in zones {
+        let xs: Vec<i16> = zone.corners.iter().map(|c| c.x.round() as i16).collect();
+        let ys: Vec<i16> = zone.corners.iter().map(|c| c.y.round() as i16).collect();
+        let centre = zone.centre();
+        let (cx, cy) = (centre.x.round() as i16, centre.y.round() as i16);
+
+        let (fill, outline) = match zone.effect {
+            ZoneEffect::Wind(_) => (Color::RGBA(60, 120, 255, 40), Color::RGB(60, 120, 255)),
+            ZoneEffect::Drag(_) => (Color::RGBA(160, 110, 60, 50), Color::RGB(160, 110, 60)),
+            ZoneEffect::Gravity(_) => (Color::RGBA(170, 60, 220, 40), Color::RGB(170, 60, 220)),
+        };
+
+        canvas.filled_polygon(&xs, &ys, fill)?;
+        canvas.polygon(&xs, &ys, outline)?;
+
+        match zone.effect {
+            ZoneEffect::Wind(acceleration) => {
+                let direction = acceleration.normalised() * (ARROW_SPACING / 3.0);
+                let (min, max) = zone.corners.iter().fold(
+                    (zone.corners[0], zone.corners[0]),
+                    |(min, max), c| {
+                        (
+                            Vec2F::new(min.x.min(c.x), min.y.min(c.y)),
+                            Vec2F::new(max.x.max(c.x), max.y.max(c.y)),
+                        )
+                    },
+                );
+
+                let columns = ((max.x - min.x) / ARROW_SPACING).ceil() as u32;
+                let rows = ((max.y - min.y) / ARROW_SPACING).ceil() as u32;
+
+                for (column, row) in (0..columns).flat_map(|c| (0..rows).map(move |r| (c, r))) {
+                    let pos = min
+                        + Vec2F::new(f64::from(column) + 0.5, f64::from(row) + 0.5) * ARROW_SPACING;
+                    if !zone.contains(pos) {
+                        continue;
+                    }
+
+                    let (tail, tip) = (pos - direction, pos + direction);
+                    let head = direction.rotated(PI * 0.8) * 0.6;
+
+                    for (from, to) in [(tail, tip), (tip, tip + head)] {
+                        canvas.line(
+                            from.x.round() as i16,
+                            from.y.round() as i16,
+                            to.x.round() as i16,
+                            to.y.round() as i16,
+                            outline,
+                        )?;
+                    }
+                }
+            }
+            ZoneEffect::Drag(drag) => {
+                canvas.string(cx - 16, cy - 3, &format!("d{drag:.3}"), outline)?;
+            }
+            ZoneEffect::Gravity(scale) => {
+                canvas.string(cx - 12, cy - 3, &format!("g{scale:.2}"), outline)?;
+            }
+        }
+
+        if show_handles {
+            for (x, y) in xs.iter().zip(&ys) {
+                canvas.circle(*x, *y, 4, Color::RGBA(255, 255, 255, 100))?;
+            }
+            canvas.filled_circle(cx, cy, 2, Color::RGBA(255, 255, 255, 100))?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn wormholes(canvas: &WindowCanvas, wormholes: &[Wormhole]) -> Result<(), String> {
+    let colour = Color::RGB(180, 100, 255);
+
+    for wormhole in wormholes {
+        let [entrance, exit] = wormhole.ends;
+        let radius = wormhole.radius.round() as i16;
+
+        canvas.line(
+            entrance.x.round() as i16,
+            entrance.y.round() as i16,
+            exit.x.round() as i16,
+            exit.y.round() as i16,
+            Color::RGBA(180, 100, 255, 50),
+        )?;
+
+        for end in wormhole.ends {
+            canvas.circle(end.x.round() as i16, end.y.round() as i16, radius, colour)?;
+            canvas.circle(
+                end.x.round() as i16,
+                end.y.round() as i16,
+                radius / 2,
+                colour,
+            )?;
+        }
+
+        // Show how the probe is turned, by where a probe flying right comes out
+        if wormhole.rotation != 0.0 {
+            let facing = Vec2F::new(wormhole.radius, 0.0);
+
+            for (end, direction) in [
+                (entrance, facing),
+                (exit, facing.rotated(wormhole.rotation)),
+            ] {
+                let tip = end + direction;
+
+                canvas.line(
+                    end.x.round() as i16,
+                    end.y.round() as i16,
+                    tip.x.round() as i16,
+                    tip.y.round() as i16,
+                    colour,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Highlight the body that ended the flight and mark where it happened
+pub fn impact(
+    canvas: &WindowCanvas,
+    simulation: &Simulation,
+    event: SimulationEvent,
+) -> Result<(), String> {
+    let highlight = Color::RGB(255, 60, 60);
+
+    match event.cause {
+        SimulationCause::Planet(i) => {
+            if let Some(planet) = simulation.planets.get(i) {
+                canvas.circle(
+                    planet.pos.x.round() as i16,
+                    planet.pos.y.round() as i16,
+                    planet.radius().round() as i16 + 2,
+                    highlight,
+                )?;
+            }
+        }
+
+        SimulationCause::Wall(i) => {
+            if let Some(wall) = simulation.walls.get(i) {
+                canvas.thick_line(
+                    wall.pos1.x.round() as i16,
+                    wall.pos1.y.round() as i16,
+                    wall.pos2.x.round() as i16,
+                    wall.pos2.y.round() as i16,
+                    2,
+                    highlight,
+                )?;
+            }
+        }
+
+        SimulationCause::Target
+        | SimulationCause::OutOfBounds
+        | SimulationCause::Timeout
+        | SimulationCause::Orbit(_) => return Ok(()),
+    }
+
+    let x = event.pos.x.round() as i16;
+    let y = event.pos.y.round() as i16;
+    canvas.line(x - 3, y - 3, x + 3, y + 3, highlight)?;
+    canvas.line(x - 3, y + 3, x + 3, y - 3, highlight)
+}
+
+/// Draw the orbits and paths of moving planets, with handles to drag them by
+pub fn planet_motions(
+    canvas: &WindowCanvas,
+    planets: &[Planet],
+    show_grab_indicators: bool,
+) -> Result<(), String> {
+    let colour = Color::RGBA(120, 90, 160, 200);
+
+    for planet in planets {
+        match &planet.motion {
+            Motion::Static => (),
+
+            Motion::Orbit { anchor, radius, .. } => {
+                let centre = match anchor {
+                    Anchor::Point(centre) => *centre,
+                    Anchor::Planet(other) => position_at(planets, *other, 0.0),
+                };
+
+                canvas.circle(
+                    centre.x.round() as i16,
+                    centre.y.round() as i16,
+                    radius.round() as i16,
+                    colour,
+                )?;
+
+                if show_grab_indicators && matches!(anchor, Anchor::Point(_)) {
+                    canvas.filled_circle(
+                        centre.x.round() as i16,
+                        centre.y.round() as i16,
+                        3,
+                        colour,
+                    )?;
+                }
+            }
+
+            Motion::Path { waypoints, .. } => {
+                for (from, to) in waypoints.iter().zip(waypoints.iter().cycle().skip(1)) {
+                    canvas.line(
+                        from.x.round() as i16,
+                        from.y.round() as i16,
+                        to.x.round() as i16,
+                        to.y.round() as i16,
+                        colour,
+                    )?;
+                }
+
+                if show_grab_indicators {
+                    for waypoint in &waypoints[1..] {
+                        canvas.circle(
+                            waypoint.x.round() as i16,
+                            waypoint.y.round() as i16,
+                            5,
+                            colour,
+                        )?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw a faded copy of each moving wall going through its motion, with a handle for where it
+/// slides to or pivots around
+pub fn wall_motions(
+    canvas: &WindowCanvas,
+    walls: &[Wall],
+    time: f64,
+    show_grab_indicators: bool,
+) -> Result<(), String> {
+    let colour = Color::RGBA(200, 200, 200, 90);
+
+    for wall in walls {
+        let Some(handle) = wall.motion_handle() else {
+            continue;
+        };
+
+        let moved = wall.at(time);
+        canvas.thick_line(
+            moved.pos1.x.round() as i16,
+            moved.pos1.y.round() as i16,
+            moved.pos2.x.round() as i16,
+            moved.pos2.y.round() as i16,
+            2,
+            colour,
+        )?;
+
+        if let WallMotion::Slide { offset, .. } = wall.motion {
+            let from = handle - offset;
+            canvas.line(
+                from.x.round() as i16,
+                from.y.round() as i16,
+                handle.x.round() as i16,
+                handle.y.round() as i16,
+                colour,
+            )?;
+        }
+
+        if show_grab_indicators {
+            canvas.circle(
+                handle.x.round() as i16,
+                handle.y.round() as i16,
+                5,
+                Color::RGB(200, 200, 200),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the Aim Mode time scrubber, and where the probe will be at that time
+pub fn time_scrubber(canvas: &WindowCanvas, context: &Context) -> Result<(), String> {
+    const LEFT: i16 = 100;
+    const WIDTH: i16 = 200;
+    const Y: i16 = 222;
+
+    let time = context.aim_time;
+    let max_ticks = context.level_data.limits.max_ticks.max(1);
+
+    let mut simulation = Simulation::empty();
+    simulation.push(&context.level_data);
+    while simulation.ticks < time && simulation.tick().is_none() {}
+
+    canvas.circle(
+        simulation.player.pos.x.round() as i16,
+        simulation.player.pos.y.round() as i16,
+        4,
+        Color::YELLOW,
+    )?;
+
+    let marker = LEFT + (f64::from(time) / f64::from(max_ticks) * f64::from(WIDTH)).round() as i16;
+    canvas.hline(LEFT, LEFT + WIDTH, Y, Color::RGB(120, 120, 120))?;
+    canvas.vline(marker, Y - 3, Y + 3, Color::YELLOW)?;
+    canvas.string(LEFT + WIDTH + 4, Y - 3, &format!("t={time}"), Color::YELLOW)
+}
+
+/// Mark where each planned burn starts and stops along the predicted flight, with an arrow
+/// showing which way it pushes the probe
+pub fn planned_burns(canvas: &WindowCanvas, context: &Context) -> Result<(), String> {
+    let colour = Color::RGB(255, 120, 40);
+    let burns = &context.level_data.burns;
+    let last_tick = burns
+        .iter()
+        .map(|burn| burn.tick + burn.duration)
+        .max()
+        .unwrap_or_default();
+
+    let mut simulation = Simulation::empty();
+    simulation.push(&context.level_data);
+
+    loop {
+        let pos = simulation.player.pos;
+        let (x, y) = (pos.x.round() as i16, pos.y.round() as i16);
+
+        for burn in burns {
+            if burn.tick == simulation.ticks {
+                let tip = pos + burn.direction * 10.0;
+                canvas.filled_circle(x, y, 2, colour)?;
+                canvas.line(x, y, tip.x.round() as i16, tip.y.round() as i16, colour)?;
+            }
+
+            if burn.tick + burn.duration == simulation.ticks {
+                canvas.circle(x, y, 2, colour)?;
+            }
+        }
+
+        if simulation.ticks >= last_tick || simulation.tick().is_some() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Point out the launch the level is designed to be solved with, longer the more powerful it is
+pub fn solution(canvas: &WindowCanvas, player: &Player, solution: Vec2F) -> Result<(), String> {
+    let colour = Color::RGB(255, 110, 200);
+    let tip = player.pos + solution * 8.0;
+
+    canvas.line(
+        player.pos.x.round() as i16,
+        player.pos.y.round() as i16,
+        tip.x.round() as i16,
+        tip.y.round() as i16,
+        colour,
+    )?;
+    canvas.circle(tip.x.round() as i16, tip.y.round() as i16, 2, colour)
+}
+
+/// Draw what's changed since another version of the level: bodies removed since in red, bodies
+/// added since in green, and arrows from where moved bodies were to where they are now
+pub fn comparison(canvas: &WindowCanvas, diff: &LevelDiff) -> Result<(), String> {
+    let removed = Color::RGB(255, 60, 60);
+    let added = Color::RGB(80, 230, 80);
+    let moved = Color::RGB(255, 220, 80);
+
+    let outline = |planet: &Planet, grow: f64, colour: Color| {
+        canvas.circle(
+            planet.pos.x.round() as i16,
+            planet.pos.y.round() as i16,
+            (planet.radius() + grow).round() as i16,
+            colour,
+        )
+    };
+    let line = |wall: &Wall, width: u8, colour: Color| {
+        canvas.thick_line(
+            wall.pos1.x.round() as i16,
+            wall.pos1.y.round() as i16,
+            wall.pos2.x.round() as i16,
+            wall.pos2.y.round() as i16,
+            width,
+            colour,
+        )
+    };
+    let arrow = |from: Vec2F, to: Vec2F| {
+        let (x, y) = (to.x.round() as i16, to.y.round() as i16);
+        canvas.line(from.x.round() as i16, from.y.round() as i16, x, y, moved)?;
+
+        let back = (from - to).normalised() * 5.0;
+        for side in [-0.5, 0.5] {
+            let barb = to + back.rotated(side);
+            canvas.line(x, y, barb.x.round() as i16, barb.y.round() as i16, moved)?;
+        }
+
+        Ok::<(), String>(())
+    };
+
+    for i in &diff.planets.removed {
+        outline(&diff.old.planets[*i], 0.0, removed)?;
+    }
+    for i in &diff.walls.removed {
+        line(&diff.old.walls[*i], 2, removed)?;
+    }
+    for j in &diff.planets.added {
+        outline(&diff.new.planets[*j], 3.0, added)?;
+    }
+    for j in &diff.walls.added {
+        line(&diff.new.walls[*j], 4, Color::RGBA(80, 230, 80, 120))?;
+    }
+
+    for (from, to) in diff.moved_planets() {
+        arrow(from, to)?;
+    }
+    for (from, to) in diff.moved_walls() {
+        line(from, 1, Color::RGBA(255, 220, 80, 120))?;
+        arrow((from.pos1 + from.pos2) * 0.5, (to.pos1 + to.pos2) * 0.5)?;
+    }
+
+    Ok(())
+}
+
+/// Draw a level small, for thumbnails. The level's top left goes at `origin`, and each of its
+/// pixels is drawn `scale` pixels across
+pub fn level_sketch(
+    canvas: &WindowCanvas,
+    level_data: &LevelData,
+    origin: Vec2F,
+    scale: f64,
+) -> Result<(), String> {
+    let point = |pos: Vec2F| {
+        let pos = origin + pos * scale;
+        (pos.x.round() as i16, pos.y.round() as i16)
+    };
+
+    for wall in &level_data.walls {
+        let ((x1, y1), (x2, y2)) = (point(wall.pos1), point(wall.pos2));
+        canvas.line(x1, y1, x2, y2, Color::RGB(200, 200, 200))?;
+    }
+
+    for planet in &level_data.planets {
+        let (x, y) = point(planet.pos);
+        let radius = (planet.radius() * scale).round().max(1.0) as i16;
+        canvas.filled_circle(x, y, radius, Color::GREY)?;
+    }
+
+    let (x, y) = point(level_data.target.pos);
+    canvas.pixel(x, y, Color::GREEN)?;
+    let (x, y) = point(level_data.player.pos);
+    canvas.pixel(x, y, Color::WHITE)
+}
+
+/// List the saved versions of the level down the right of the screen, each with a thumbnail, its
+/// number and how long ago it was saved. The level being edited is marked with a `*`
+pub fn history(canvas: &WindowCanvas, history: &History, level_path: &str) -> Result<(), String> {
+    const LEFT: i16 = 246;
+    const TOP: i16 = 12;
+    const ROW_HEIGHT: i16 = 38;
+    const THUMBNAIL_SCALE: f64 = 0.125;
+
+    let right = GRID_X_SIZE as i16 - 1;
+    let rows = ((GRID_Y_SIZE as i16 - TOP) / ROW_HEIGHT) as usize;
+    let now = SystemTime::now();
+
+    canvas.box_(
+        LEFT,
+        0,
+        right,
+        GRID_Y_SIZE as i16 - 1,
+        Color::RGBA(0, 0, 0, 220),
+    )?;
+    canvas.string(
+        LEFT + 4,
+        2,
+        &format!("History ({})", history.versions.len()),
+        Color::WHITE,
+    )?;
+
+    let first = history.selected.saturating_sub(rows - 1);
+    let shown = history.versions.iter().enumerate().skip(first).take(rows);
+    for (top, (i, version)) in (0..).map(|row| TOP + row * ROW_HEIGHT).zip(shown) {
+        if i == history.selected {
+            canvas.box_(
+                LEFT + 1,
+                top,
+                right - 1,
+                top + ROW_HEIGHT - 2,
+                Color::RGBA(80, 80, 160, 160),
+            )?;
+        }
+
+        let (x, y) = (LEFT + 4, top + 3);
+        canvas.rectangle(x - 1, y - 1, x + 51, y + 31, Color::RGB(120, 120, 120))?;
+        match &version.level_data {
+            Ok(level_data) => level_sketch(
+                canvas,
+                level_data,
+                Vec2F::new(f64::from(x), f64::from(y)),
+                THUMBNAIL_SCALE,
+            )?,
+            Err(_) => canvas.string(x + 22, y + 11, "?", Color::RED)?,
+        }
+
+        let current = if version.path == level_path { " *" } else { "" };
+        canvas.string(
+            x + 54,
+            top + 6,
+            &format!("{}{current}", version.label()),
+            Color::WHITE,
+        )?;
+        canvas.string(
+            x + 54,
+            top + 18,
+            &version.age(now),
+            Color::RGB(160, 160, 160),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The colour problems of each severity are shown in
+pub const fn severity_colour(severity: Severity) -> Color {
+    match severity {
+        Severity::Warning => Color::RGB(255, 170, 60),
+        Severity::Error => Color::RGB(255, 60, 60),
+    }
+}
+
+/// Circle where a problem with the level is, if it's somewhere in particular
+pub fn diagnostic_marker(canvas: &WindowCanvas, diagnostic: &Diagnostic) -> Result<(), String> {
+    let Some(pos) = diagnostic.pos else {
+        return Ok(());
+    };
+
+    canvas.circle(
+        pos.x.round() as i16,
+        pos.y.round() as i16,
+        6,
+        severity_colour(diagnostic.severity),
+    )
+}
+
+/// Draw a flame behind the probe while its engine is burning
+pub fn exhaust(canvas: &WindowCanvas, simulation: &Simulation) -> Result<(), String> {
+    let Some(direction) = simulation.burn_direction() else {
+        return Ok(());
+    };
+
+    let pos = simulation.player.pos;
+    let flame = pos - direction * 6.0;
+
+    canvas.thick_line(
+        pos.x.round() as i16,
+        pos.y.round() as i16,
+        flame.x.round() as i16,
+        flame.y.round() as i16,
+        2,
+        Color::RGB(255, 120, 40),
+    )
+}
+
+/// Show how far through a replay playback is, with marks where the player paused (yellow) or
+/// changed their thrust or speed (orange)
+pub fn replay_scrubber(canvas: &WindowCanvas, playback: &Playback) -> Result<(), String> {
+    const LEFT: i16 = 100;
+    const WIDTH: i16 = 200;
+    const Y: i16 = 222;
+
+    let length = f64::from(playback.end.tick.max(1));
+    let x = |tick: u32| LEFT + (f64::from(tick) / length * f64::from(WIDTH)).round() as i16;
+
+    canvas.hline(LEFT, LEFT + WIDTH, Y, Color::RGB(120, 120, 120))?;
+
+    for (tick, input) in &playback.replay.inputs {
+        let colour = if *input == ReplayInput::Pause {
+            Color::YELLOW
+        } else {
+            Color::RGB(255, 120, 40)
+        };
+        canvas.vline(x(*tick), Y - 2, Y + 2, colour)?;
+    }
+
+    let playing = match playback.direction {
+        0 => "||",
+        1 => ">",
+        _ => "<",
+    };
+
+    canvas.vline(x(playback.tick), Y - 4, Y + 4, Color::WHITE)?;
+    canvas.string(
+        LEFT + WIDTH + 4,
+        Y - 3,
+        &format!("{playing} t={}/{}", playback.tick, playback.end.tick),
+        Color::WHITE,
+    )
+}
+
+/// Show the tick of the flight being looked at, out of the furthest the flight has got
+pub fn flight_timeline(canvas: &WindowCanvas, context: &Context) -> Result<(), String> {
+    let tick = context.simulation.ticks;
+    let length = context.timeline.length(&context.simulation);
+    let marker = BAR_LEFT
+        + (f64::from(tick) / f64::from(length.max(1)) * f64::from(BAR_WIDTH)).round() as i16;
+
+    canvas.hline(
+        BAR_LEFT,
+        BAR_LEFT + BAR_WIDTH,
+        BAR_Y,
+        Color::RGB(120, 120, 120),
+    )?;
+    canvas.vline(marker, BAR_Y - 4, BAR_Y + 4, Color::WHITE)?;
+    canvas.string(
+        BAR_LEFT + BAR_WIDTH + 4,
+        BAR_Y - 3,
+        &format!("t={tick}/{length}"),
+        Color::WHITE,
+    )
+}
+
+/// Draw what's acting on the probe this tick: its velocity (white), each planet's pull (blue,
+/// or red for antiplanets) and the total of every force on it, including wind and thrust
+/// (orange)
+pub fn forces(canvas: &WindowCanvas, simulation: &Simulation) -> Result<(), String> {
+    const VELOCITY_SCALE: f64 = 10.0;
+    const FORCE_SCALE: f64 = 400.0;
+    const LONGEST: f64 = 60.0;
+
+    let pos = simulation.player.pos;
+    let arrow = |vector: Vec2F, scale: f64, colour: Color| {
+        let length = (vector.magnitude() * scale).min(LONGEST);
+        let tip = pos + vector.normalised() * length;
+        canvas.line(
+            pos.x.round() as i16,
+            pos.y.round() as i16,
+            tip.x.round() as i16,
+            tip.y.round() as i16,
+            colour,
+        )
+    };
+
+    let (gravity_scale, _, _) = simulation.zone_effects(pos);
+    for (planet, pull) in simulation.planets.iter().zip(simulation.pulls_at(pos)) {
+        let colour = if matches!(planet.kind, PlanetKind::Anti) {
+            Color::RGBA(255, 90, 90, 160)
+        } else {
+            Color::RGBA(90, 140, 255, 160)
+        };
+        arrow(pull * gravity_scale, FORCE_SCALE, colour)?;
+    }
+
+    let thrust = simulation
+        .burn_direction()
+        .map_or(Vec2F::ZERO, |direction| direction * THRUST);
+    arrow(
+        simulation.acceleration_at(pos) + thrust,
+        FORCE_SCALE,
+        Color::RGB(255, 160, 40),
+    )?;
+    arrow(simulation.player.velocity, VELOCITY_SCALE, Color::WHITE)
+}
+
+/// Draw the mirror lines or rotation spokes of the current symmetry
+pub fn symmetry_guides(canvas: &WindowCanvas, symmetry: Symmetry) -> Result<(), String> {
+    let colour = Color::RGBA(80, 80, 160, 160);
+    let centre = symmetry.centre;
+
+    if matches!(symmetry.mode, SymmetryMode::Horizontal | SymmetryMode::Both) {
+        canvas.vline(centre.x.round() as i16, 0, GRID_Y_SIZE as i16, colour)?;
+    }
+
+    if matches!(symmetry.mode, SymmetryMode::Vertical | SymmetryMode::Both) {
+        canvas.hline(0, GRID_X_SIZE as i16, centre.y.round() as i16, colour)?;
+    }
+
+    if let SymmetryMode::Rotational(_) = symmetry.mode {
+        let spoke = centre + Vec2F::new(0.0, -(GRID_X_SIZE as f64));
+
+        for transform in 0..symmetry.order() {
+            let end = symmetry.apply(transform, spoke);
+
+            canvas.line(
+                centre.x.round() as i16,
+                centre.y.round() as i16,
+                end.x.round() as i16,
+                end.y.round() as i16,
+                colour,
+            )?;
+        }
+    }
+
+    if symmetry.mode != SymmetryMode::Off {
+        canvas.circle(centre.x.round() as i16, centre.y.round() as i16, 3, colour)?;
+    }
+
+    Ok(())
+}
+
+/// Draw every pinned ghost's path as the level is now, in its own colour. A circle marks where
+/// it splits off from the launch being aimed, and the label at the end says how it ends, and how
+/// it ended when it was pinned if that's changed since
+pub fn ghosts(
+    canvas: &WindowCanvas,
+    context: &Context,
+    flights: &ghost::Flights,
+) -> Result<(), String> {
+    const COLOURS: [(u8, u8, u8); 5] = [
+        (90, 220, 255),
+        (255, 110, 200),
+        (180, 255, 90),
+        (255, 200, 80),
+        (170, 150, 255),
+    ];
+
+    let aiming = context.level_data.player.velocity != Vec2F::ZERO;
+    let live = &flights.live;
+
+    for (i, (ghost, flight)) in context.ghosts.iter().zip(&flights.ghosts).enumerate() {
+        let (r, g, b) = COLOURS[i % COLOURS.len()];
+        let colour = Color::RGB(r, g, b);
+
+        for leg in flight.path.windows(2) {
+            canvas.line(
+                leg[0].x.round() as i16,
+                leg[0].y.round() as i16,
+                leg[1].x.round() as i16,
+                leg[1].y.round() as i16,
+                Color::RGBA(r, g, b, 140),
+            )?;
+        }
+
+        if let Some(tick) = ghost::divergence(&flight.path, &live.path).filter(|_| aiming) {
+            let pos = flight.path[tick];
+            canvas.circle(pos.x.round() as i16, pos.y.round() as i16, 3, colour)?;
+        }
+
+        let end = flight.path[flight.path.len() - 1];
+        let label = if flight.event.cause == ghost.pinned {
+            format!("{} {}", ghost.name, flight.event.cause)
+        } else {
+            format!("{} {} -> {}", ghost.name, ghost.pinned, flight.event.cause)
+        };
+        canvas.string(
+            end.x.round() as i16 + 4,
+            end.y.round() as i16 - 3,
+            &label,
+            colour,
+        )?;
+    }
+
+    Ok(())
+}
+
 pub fn trajectory(
     canvas: &mut WindowCanvas,
     context: &Context,
-    count: i32,
-    spacing: i32,
+    count: u32,
+    spacing: u32,
     colour: Color,
 ) -> Result<(), String> {
     let mut simulation = Simulation::empty();
@@ -141,7 +1030,7 @@ pub fn trajectory(
             }
         }
 
-        canvas.set_draw_color(colour);
+        canvas.set_draw_color(checkpoint_colour(colour, simulation.checkpoint_progress()));
         canvas.draw_line(last_pos, simulation.player.pos)?;
 
         last_pos = simulation.player.pos;
@@ -149,3 +1038,92 @@ pub fn trajectory(
 
     Ok(())
 }
+
+/// Plot how the total energy of the predicted flight drifts from its launch value under each
+/// integrator. A level whose outcome depends on the integrator is relying on numerical error
+pub fn energy_plot(canvas: &WindowCanvas, context: &Context) -> Result<(), String> {
+    const LEFT: i16 = 240;
+    const TOP: i16 = 160;
+    const WIDTH: i16 = 156;
+    const HEIGHT: i16 = 60;
+
+    let max_ticks = context.level_data.limits.max_ticks as usize;
+
+    let drifts: Vec<(Integrator, Vec<f64>)> = Integrator::ALL
+        .into_iter()
+        .map(|integrator| {
+            let mut simulation = Simulation::empty();
+            simulation.push(&context.level_data);
+            simulation.physics.integrator = integrator;
+
+            let launch_energy = simulation.energy();
+            let mut drift = Vec::new();
+            while drift.len() < max_ticks && simulation.tick().is_none() {
+                drift.push(simulation.energy() - launch_energy);
+            }
+
+            (integrator, drift)
+        })
+        .collect();
+
+    let longest = drifts
+        .iter()
+        .map(|(_, d)| d.len())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let largest = drifts
+        .iter()
+        .flat_map(|(_, d)| d.iter())
+        .fold(1e-9_f64, |largest, drift| largest.max(drift.abs()));
+
+    canvas.box_(
+        LEFT,
+        TOP,
+        LEFT + WIDTH,
+        TOP + HEIGHT,
+        Color::RGBA(0, 0, 0, 200),
+    )?;
+    canvas.hline(LEFT, LEFT + WIDTH, TOP + HEIGHT / 2, Color::RGB(60, 60, 60))?;
+
+    for (integrator, drift) in &drifts {
+        let colour = match integrator {
+            Integrator::SemiImplicitEuler => Color::RED,
+            Integrator::VelocityVerlet => Color::GREEN,
+            Integrator::RungeKutta4 => Color::CYAN,
+        };
+
+        let point = |i: usize, value: f64| {
+            (
+                LEFT + (i * WIDTH as usize / longest) as i16,
+                TOP + HEIGHT / 2 - (value / largest * f64::from(HEIGHT / 2 - 1)).round() as i16,
+            )
+        };
+
+        for (i, pair) in drift.windows(2).enumerate() {
+            let (x1, y1) = point(i, pair[0]);
+            let (x2, y2) = point(i + 1, pair[1]);
+            canvas.line(x1, y1, x2, y2, colour)?;
+        }
+
+        let marker = if *integrator == context.level_data.physics.integrator {
+            ">"
+        } else {
+            " "
+        };
+        let legend_y = TOP + 2 + 8 * (*integrator as i16);
+        canvas.string(
+            LEFT + 2,
+            legend_y,
+            &format!("{marker}{}", integrator.name()),
+            colour,
+        )?;
+    }
+
+    canvas.string(
+        LEFT + 2,
+        TOP + HEIGHT - 9,
+        &format!("energy drift +-{largest:.3}"),
+        Color::WHITE,
+    )
+}
@@ -0,0 +1,248 @@
+//! The planets' combined gravity, sampled once and drawn from the samples until the planets change
+
+use sdl2::{gfx::primitives::DrawRenderer, pixels::Color, render::WindowCanvas};
+
+use super::{GRID_X_SIZE, GRID_Y_SIZE};
+use crate::app::context::{FieldOverlay, Simulation, Vec2F};
+
+/// How many pixels apart the field is sampled for the heatmap and contours
+const CELL: i16 = 4;
+/// How many pixels apart the arrows are
+const ARROW_SPACING: i16 = 16;
+/// The potential of the innermost contours, and how many there are on each side of zero. Each
+/// contour is at half the potential of the one inside it
+const INNER_POTENTIAL: f64 = 32.0;
+const CONTOURS: i32 = 9;
+/// The weakest and strongest field strengths told apart, as powers of ten
+const WEAKEST: f64 = -3.0;
+const STRONGEST: f64 = 0.0;
+
+/// Everything about the planets the field depends on: the strength of gravity, then each
+/// planet's position, pull and crash radius squared
+type Key = (f64, Vec<(Vec2F, f64, Option<f64>)>);
+
+pub struct Field {
+    key: Key,
+    /// The middle of every cell and the strength of the field there, for the heatmap
+    strengths: Vec<(Vec2F, f64)>,
+    /// The ends of every piece of every contour line
+    contours: Vec<(Vec2F, Vec2F)>,
+    /// Where each arrow starts, and the field there
+    arrows: Vec<(Vec2F, Vec2F)>,
+    /// Each planet's centre and crash radius
+    crash_circles: Vec<(Vec2F, f64)>,
+}
+
+impl Field {
+    fn key(simulation: &Simulation) -> Key {
+        let planets = simulation
+            .planets
+            .iter()
+            .map(|planet| {
+                (
+                    planet.pos,
+                    planet.pull(),
+                    planet.crash_radius_squared(&simulation.physics),
+                )
+            })
+            .collect();
+
+        (simulation.physics.gravity, planets)
+    }
+
+    /// Sample the field of the simulation's planets across the whole level
+    pub fn new(simulation: &Simulation) -> Self {
+        let field_at = |pos: Vec2F| {
+            simulation
+                .pulls_at(pos)
+                .fold(Vec2F::ZERO, |total, pull| total + pull)
+        };
+
+        let strengths = grid(CELL)
+            .map(|pos| {
+                let centre = pos + Vec2F::new(f64::from(CELL), f64::from(CELL)) * 0.5;
+                (pos, field_at(centre).magnitude())
+            })
+            .collect();
+
+        let arrows = grid(ARROW_SPACING)
+            .map(|pos| {
+                let centre =
+                    pos + Vec2F::new(f64::from(ARROW_SPACING), f64::from(ARROW_SPACING)) * 0.5;
+                (centre, field_at(centre))
+            })
+            .collect();
+
+        let crash_circles = simulation
+            .planets
+            .iter()
+            .filter_map(|planet| {
+                planet
+                    .crash_radius_squared(&simulation.physics)
+                    .map(|radius_squared| (planet.pos, radius_squared.sqrt()))
+            })
+            .collect();
+
+        Self {
+            key: Self::key(simulation),
+            strengths,
+            contours: contours(simulation),
+            arrows,
+            crash_circles,
+        }
+    }
+
+    /// Whether the field was sampled from planets just like the simulation's
+    pub fn is_for(&self, simulation: &Simulation) -> bool {
+        self.key == Self::key(simulation)
+    }
+
+    pub fn draw(&self, canvas: &WindowCanvas, overlay: FieldOverlay) -> Result<(), String> {
+        match overlay {
+            FieldOverlay::Off => return Ok(()),
+
+            FieldOverlay::Heatmap => {
+                for (pos, strength) in &self.strengths {
+                    let (x, y) = (pos.x as i16, pos.y as i16);
+                    canvas.box_(x, y, x + CELL - 1, y + CELL - 1, heat(*strength))?;
+                }
+            }
+
+            FieldOverlay::Contours => {
+                for (from, to) in &self.contours {
+                    canvas.line(
+                        from.x.round() as i16,
+                        from.y.round() as i16,
+                        to.x.round() as i16,
+                        to.y.round() as i16,
+                        Color::RGBA(120, 200, 255, 140),
+                    )?;
+                }
+            }
+
+            FieldOverlay::Arrows => {
+                for (pos, field) in &self.arrows {
+                    arrow(canvas, *pos, *field)?;
+                }
+            }
+        }
+
+        for (pos, radius) in &self.crash_circles {
+            canvas.filled_circle(
+                pos.x.round() as i16,
+                pos.y.round() as i16,
+                radius.round() as i16,
+                Color::RGBA(255, 60, 60, 70),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The top left corner of every square of `size` pixels covering the level, row by row
+fn grid(size: i16) -> impl Iterator<Item = Vec2F> {
+    let (columns, rows) = (GRID_X_SIZE as i16 / size, GRID_Y_SIZE as i16 / size);
+
+    (0..rows).flat_map(move |row| {
+        (0..columns).map(move |column| Vec2F::new(f64::from(column * size), f64::from(row * size)))
+    })
+}
+
+/// How strong a field is between [`WEAKEST`] and [`STRONGEST`], from 0 to 1
+fn strength_fraction(strength: f64) -> f64 {
+    ((strength.log10() - WEAKEST) / (STRONGEST - WEAKEST)).clamp(0.0, 1.0)
+}
+
+/// The heatmap colour of a field strength, from blue through red to yellow
+fn heat(strength: f64) -> Color {
+    let t = strength_fraction(strength);
+
+    let (r, g, b) = if t < 0.5 {
+        let t = t * 2.0;
+        (t * 200.0, 0.0, (1.0 - t) * 160.0)
+    } else {
+        let t = t.mul_add(2.0, -1.0);
+        (t.mul_add(55.0, 200.0), t * 230.0, 0.0)
+    };
+
+    Color::RGBA(r as u8, g as u8, b as u8, 110)
+}
+
+/// An arrow pointing the way `field` pulls, longer the stronger it is
+fn arrow(canvas: &WindowCanvas, pos: Vec2F, field: Vec2F) -> Result<(), String> {
+    let colour = Color::RGBA(255, 255, 255, 150);
+    let direction = field.normalised();
+    let tip = pos + direction * strength_fraction(field.magnitude()).mul_add(9.0, 3.0);
+
+    canvas.line(
+        pos.x.round() as i16,
+        pos.y.round() as i16,
+        tip.x.round() as i16,
+        tip.y.round() as i16,
+        colour,
+    )?;
+
+    for side in [-2.5, 2.5] {
+        let barb = tip + direction.rotated(side) * 2.0;
+        canvas.line(
+            tip.x.round() as i16,
+            tip.y.round() as i16,
+            barb.x.round() as i16,
+            barb.y.round() as i16,
+            colour,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Trace lines of equal potential through a grid of samples, with marching squares
+fn contours(simulation: &Simulation) -> Vec<(Vec2F, Vec2F)> {
+    let (columns, rows) = (GRID_X_SIZE as i16 / CELL, GRID_Y_SIZE as i16 / CELL);
+    let corner = |column: i16, row: i16| {
+        let pos = Vec2F::new(f64::from(column * CELL), f64::from(row * CELL));
+        (pos, simulation.potential_at(pos))
+    };
+
+    let levels: Vec<f64> = (0..CONTOURS)
+        .map(|n| INNER_POTENTIAL / 2_f64.powi(n))
+        .flat_map(|potential| [-potential, potential])
+        .collect();
+
+    let mut segments = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            // Clockwise from the top left, so crossings come out in order around the cell
+            let corners = [
+                corner(column, row),
+                corner(column + 1, row),
+                corner(column + 1, row + 1),
+                corner(column, row + 1),
+            ];
+            if corners.iter().any(|(_, potential)| !potential.is_finite()) {
+                continue;
+            }
+
+            for level in &levels {
+                let crossings: Vec<Vec2F> = (0..4)
+                    .filter_map(|edge| {
+                        let (a, a_potential) = corners[edge];
+                        let (b, b_potential) = corners[(edge + 1) % 4];
+
+                        ((a_potential - level) * (b_potential - level) < 0.0).then(|| {
+                            let along = (level - a_potential) / (b_potential - a_potential);
+                            a + (b - a) * along
+                        })
+                    })
+                    .collect();
+
+                for pair in crossings.chunks_exact(2) {
+                    segments.push((pair[0], pair[1]));
+                }
+            }
+        }
+    }
+
+    segments
+}
@@ -3,6 +3,17 @@ use rfd::{MessageButtons, MessageDialog, MessageLevel};
 mod app;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some(result) = app::cli::run(&args) {
+        let code = result.unwrap_or_else(|err| {
+            eprintln!("{err}");
+            2
+        });
+
+        std::process::exit(code);
+    }
+
     let r = app::main();
 
     if let Err(err) = r {